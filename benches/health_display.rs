@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+#[path = "../src/health_display.rs"]
+mod health_display;
+
+use health_display::{display_health, Health, HealthDisplay};
+
+// Runs the game's health display system over a crowd of enemies, each showing one of the shared
+// fill meshes. Most frames nobody's health changes, so compare that against every enemy being hit
+// at once.
+
+const MAX_HP: u32 = 10;
+
+#[derive(Component)]
+struct Enemy {
+  hp: u32,
+}
+
+impl Health for Enemy {
+  fn hp(&self) -> u32 {
+    self.hp
+  }
+
+  fn max_hp(&self) -> u32 {
+    MAX_HP
+  }
+}
+
+fn spawn_enemies(world: &mut World, count: usize) {
+  let fills = (0..=MAX_HP)
+    .map(|_| Handle::<Mesh>::weak_from_u128(rand::random()))
+    .collect::<Vec<_>>();
+  for _ in 0..count {
+    world.spawn(Enemy { hp: MAX_HP }).with_child((
+      Mesh2d(fills[MAX_HP as usize].clone()),
+      HealthDisplay {
+        fills: fills.clone(),
+      },
+    ));
+  }
+}
+
+// Killed enemies go back to full, so every frame has the same amount of work.
+fn hit_every_enemy(mut enemies: Query<&mut Enemy>) {
+  for mut enemy in enemies.iter_mut() {
+    enemy.hp = enemy.hp.checked_sub(1).unwrap_or(MAX_HP);
+  }
+}
+
+fn bench_health_display(c: &mut Criterion) {
+  let mut group = c.benchmark_group("health_display");
+  for count in [100, 500, 2000] {
+    group.bench_with_input(BenchmarkId::new("unchanged", count), &count, |b, &count| {
+      let mut world = World::new();
+      spawn_enemies(&mut world, count);
+      let mut schedule = Schedule::default();
+      schedule.add_systems(display_health::<Enemy>);
+      // The first run sees every enemy as newly added.
+      schedule.run(&mut world);
+      b.iter(|| schedule.run(&mut world));
+    });
+    group.bench_with_input(BenchmarkId::new("all_hit", count), &count, |b, &count| {
+      let mut world = World::new();
+      spawn_enemies(&mut world, count);
+      let mut schedule = Schedule::default();
+      schedule.add_systems((hit_every_enemy, display_health::<Enemy>).chain());
+      b.iter(|| schedule.run(&mut world));
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_health_display);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Its unit tests are compiled here too but never run, so their helpers look unused.
+#[path = "../src/spatial_grid.rs"]
+#[allow(unused)]
+mod spatial_grid;
+
+use spatial_grid::SpatialGrid;
+
+// About the size of the play area, with enemy-sized boxes scattered across it.
+const AREA: Vec2 = Vec2::new(16., 9.);
+const HALF_SIZE: f32 = 0.25;
+
+fn random_boxes(count: usize) -> Vec<Rect> {
+  let mut rng = StdRng::seed_from_u64(0);
+  (0..count)
+    .map(|_| {
+      let center = Vec2::new(rng.gen_range(0. ..AREA.x), rng.gen_range(0. ..AREA.y));
+      Rect::from_center_half_size(center, Vec2::splat(HALF_SIZE))
+    })
+    .collect()
+}
+
+fn overlaps(a: &Rect, b: &Rect) -> bool {
+  !a.intersect(*b).is_empty()
+}
+
+fn brute_force_pairs(boxes: &[Rect]) -> usize {
+  let mut pairs = 0;
+  for (i, a) in boxes.iter().enumerate() {
+    for b in boxes[i + 1..].iter() {
+      if overlaps(a, b) {
+        pairs += 1;
+      }
+    }
+  }
+  pairs
+}
+
+fn grid_pairs(grid: &mut SpatialGrid<usize>, boxes: &[Rect]) -> usize {
+  grid.clear();
+  for (i, aabb) in boxes.iter().enumerate() {
+    grid.insert(i, *aabb);
+  }
+  boxes
+    .iter()
+    .enumerate()
+    .map(|(i, aabb)| {
+      grid
+        .query_rect(*aabb)
+        .filter(|&j| i < j && overlaps(aabb, &boxes[j]))
+        .count()
+    })
+    .sum()
+}
+
+fn broadphase(c: &mut Criterion) {
+  let mut group = c.benchmark_group("broadphase");
+  for count in [250, 1000, 4000] {
+    let boxes = random_boxes(count);
+    let mut grid = SpatialGrid::new(1.0);
+    assert_eq!(brute_force_pairs(&boxes), grid_pairs(&mut grid, &boxes));
+
+    group.bench_with_input(
+      BenchmarkId::new("brute_force", count),
+      &boxes,
+      |b, boxes| b.iter(|| brute_force_pairs(boxes)),
+    );
+    group.bench_with_input(BenchmarkId::new("grid", count), &boxes, |b, boxes| {
+      b.iter(|| grid_pairs(&mut grid, boxes))
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, broadphase);
+criterion_main!(benches);
@@ -0,0 +1,306 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+  collision::{Collider, CollisionEvent, CollisionLayer, DetectCollisionsSet},
+  damage::{DamageEvent, DamageSource, DetectDamageSet},
+  path::Path,
+  playing::{Enemy, Player},
+  window_setup::PlayArea,
+  AppState, EnableStateScopedResource, GameMode,
+};
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .enable_state_scoped_resource::<ArenaBlockers>(AppState::Playing)
+      .add_systems(OnEnter(AppState::Playing), spawn_arena)
+      .add_systems(
+        Update,
+        (
+          (block_player, bounce_enemies).after(DetectCollisionsSet),
+          hazard_damage.in_set(DetectDamageSet),
+        )
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+const HAZARD_DAMAGE: u32 = 2;
+// Half as thick as the thinnest wall, so a sweep can't step over any blocker.
+const SWEEP_STEP: f32 = 0.1;
+
+const OBSTACLE_COLOR: Color = Color::hsl(230., 0.1, 0.45);
+const WALL_COLOR: Color = Color::hsl(230., 0.15, 0.65);
+const HAZARD_COLOR: Color = Color::hsla(0., 0.9, 0.5, 0.25);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arena {
+  Open,
+  Pillars,
+  Corridors,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PieceKind {
+  Obstacle,
+  Wall,
+  Hazard,
+}
+
+struct ArenaPiece {
+  kind: PieceKind,
+  // Must be convex and wound counterclockwise. In world units, so pieces keep their shape.
+  outline: Path,
+  // As a fraction of the play area's half size, so layouts fit any window.
+  position: Vec2,
+}
+
+impl Arena {
+  fn for_mode(mode: GameMode) -> Self {
+    match mode {
+      GameMode::DashSwap => Arena::Pillars,
+      GameMode::BombSurprise => Arena::Corridors,
+      GameMode::Shoot => Arena::Open,
+    }
+  }
+
+  fn pieces(self, half_size: Vec2) -> Vec<ArenaPiece> {
+    let piece = |kind, outline, x, y| ArenaPiece {
+      kind,
+      outline,
+      position: Vec2::new(x, y),
+    };
+    let pillar = |x, y| piece(PieceKind::Obstacle, Path::rect(Vec2::splat(0.4)), x, y);
+    let corner_hazard = |x, y| piece(PieceKind::Hazard, Path::rect(Vec2::splat(0.6)), x, y);
+    match self {
+      Arena::Open => vec![
+        piece(PieceKind::Obstacle, polygon_outline(0.5, 6), -0.5, 0.),
+        piece(PieceKind::Obstacle, polygon_outline(0.5, 6), 0.5, 0.),
+      ],
+      // Pillars to dash between, with a hazard in the middle so the center isn't a safe spot.
+      Arena::Pillars => vec![
+        pillar(-0.5, -0.45),
+        pillar(0.5, -0.45),
+        pillar(-0.5, 0.45),
+        pillar(0.5, 0.45),
+        piece(PieceKind::Wall, Path::rect(Vec2::new(1.5, 0.1)), 0., -0.6),
+        piece(PieceKind::Wall, Path::rect(Vec2::new(1.5, 0.1)), 0., 0.6),
+        piece(PieceKind::Hazard, polygon_outline(0.8, 6), 0., 0.),
+      ],
+      // Walls split the arena into three lanes, with gaps at the ends to get between them.
+      Arena::Corridors => vec![
+        piece(
+          PieceKind::Wall,
+          Path::rect(Vec2::new(0.1, half_size.y * 0.7)),
+          -0.35,
+          0.,
+        ),
+        piece(
+          PieceKind::Wall,
+          Path::rect(Vec2::new(0.1, half_size.y * 0.7)),
+          0.35,
+          0.,
+        ),
+        piece(PieceKind::Obstacle, polygon_outline(0.6, 4), 0., 0.),
+        corner_hazard(-0.85, -0.75),
+        corner_hazard(0.85, 0.75),
+      ],
+    }
+  }
+}
+
+// The arena's walls and obstacles, for moves too big for pushing the player back out afterwards to
+// catch, like swaps. Pushing out of something deep inside it can put the player on its far side, so
+// these moves are swept instead. Only exists while playing.
+#[derive(Resource, Debug, Default)]
+pub struct ArenaBlockers(Vec<(Collider, Transform)>);
+
+impl ArenaBlockers {
+  // Where a collider moving in a straight line stops, up against the near side of the first
+  // blocker in its way.
+  pub fn sweep(&self, collider: &Collider, from: Vec2, to: Vec2) -> Vec2 {
+    let steps = ((to - from).length() / SWEEP_STEP).ceil().max(1.) as u32;
+    for step in 1..=steps {
+      let position = from.lerp(to, step as f32 / steps as f32);
+      let transform = Transform::from_translation(position.extend(0.));
+      for (blocker_collider, blocker_transform) in &self.0 {
+        if let Some(push) = collider.penetration(&transform, blocker_collider, blocker_transform) {
+          return position + push;
+        }
+      }
+    }
+    to
+  }
+}
+
+// With a corner pointing along +x, unlike Path::regular_polygon.
+fn polygon_outline(radius: f32, sides: u32) -> Path {
+  Path::polygon((0..sides).map(|i| Vec2::from_angle(TAU * i as f32 / sides as f32) * radius))
+}
+
+fn spawn_arena(
+  mut commands: Commands,
+  mode: Res<GameMode>,
+  play_area: Res<PlayArea>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+  let half_size = play_area.size_world / 2.;
+  let mut blockers = ArenaBlockers::default();
+  for piece in Arena::for_mode(*mode).pieces(half_size) {
+    let (layer, color) = match piece.kind {
+      PieceKind::Obstacle => (CollisionLayer::Obstacle, OBSTACLE_COLOR),
+      PieceKind::Wall => (CollisionLayer::Wall, WALL_COLOR),
+      PieceKind::Hazard => (CollisionLayer::Hazard, HAZARD_COLOR),
+    };
+    let position = piece.position * half_size;
+    let transform = Transform::from_translation(position.extend(-1.0));
+    let collider = Collider::convex_from_path(&piece.outline);
+    if matches!(piece.kind, PieceKind::Obstacle | PieceKind::Wall) {
+      blockers.0.push((collider.clone(), transform));
+    }
+    commands.spawn((
+      transform,
+      collider,
+      layer,
+      Mesh2d(
+        meshes.add(
+          piece
+            .outline
+            .build_triangle_mesh()
+            .expect("arena pieces are simple polygons"),
+        ),
+      ),
+      MeshMaterial2d(materials.add(color)),
+      StateScoped(AppState::Playing),
+    ));
+  }
+  commands.insert_resource(blockers);
+}
+
+// Push the player back out of anything solid they moved into. Moves that could carry them into or
+// past a wall or obstacle are swept against ArenaBlockers instead.
+fn block_player(
+  mut collision_events: EventReader<CollisionEvent>,
+  mut player: Query<(&Collider, &mut Transform), With<Player>>,
+  blockers: Query<(&Collider, &Transform), Without<Player>>,
+) {
+  for event in collision_events.read() {
+    let Some((player_entity, blocker)) = event
+      .between(CollisionLayer::Player, CollisionLayer::Obstacle)
+      .or_else(|| event.between(CollisionLayer::Player, CollisionLayer::Wall))
+    else {
+      continue;
+    };
+    let Ok((player_collider, mut player_transform)) = player.get_mut(player_entity) else {
+      continue;
+    };
+    let Ok((blocker_collider, blocker_transform)) = blockers.get(blocker) else {
+      continue;
+    };
+
+    // Checked again, since an earlier push this frame may have already cleared this one.
+    if let Some(push) =
+      player_collider.penetration(&player_transform, blocker_collider, blocker_transform)
+    {
+      player_transform.translation += push.extend(0.);
+    }
+  }
+}
+
+fn bounce_enemies(
+  mut collision_events: EventReader<CollisionEvent>,
+  mut enemies: Query<(&Collider, &mut Transform, &mut Enemy)>,
+  obstacles: Query<(&Collider, &Transform), Without<Enemy>>,
+) {
+  for (enemy, obstacle) in collision_events
+    .read()
+    .filter_map(|event| event.between(CollisionLayer::Enemy, CollisionLayer::Obstacle))
+  {
+    let Ok((enemy_collider, mut enemy_transform, mut enemy)) = enemies.get_mut(enemy) else {
+      continue;
+    };
+    let Ok((obstacle_collider, obstacle_transform)) = obstacles.get(obstacle) else {
+      continue;
+    };
+
+    if let Some(push) =
+      enemy_collider.penetration(&enemy_transform, obstacle_collider, obstacle_transform)
+    {
+      enemy_transform.translation += push.extend(0.);
+      if let Some(normal) = push.try_normalize() {
+        enemy.bounce(normal);
+      }
+    }
+  }
+}
+
+// Hazards, both the arena's and the timed ones, hurt like touching an enemy does, so hit
+// invulnerability keeps them from shredding the player. Only the player collides with them.
+fn hazard_damage(
+  mut collision_events: EventReader<CollisionEvent>,
+  player: Query<&Transform, With<Player>>,
+  mut damage_events: EventWriter<DamageEvent>,
+) {
+  // One hit per frame, however many hazards the player is standing in.
+  let Some((player_entity, _)) = collision_events
+    .read()
+    .filter_map(|event| event.between(CollisionLayer::Player, CollisionLayer::Hazard))
+    .last()
+  else {
+    return;
+  };
+  let Ok(transform) = player.get(player_entity) else {
+    return;
+  };
+
+  damage_events.send(DamageEvent {
+    target: player_entity,
+    amount: HAZARD_DAMAGE,
+    source: DamageSource::Contact,
+    attack: None,
+    position: transform.translation.xy(),
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pillar_at(position: Vec2) -> ArenaBlockers {
+    ArenaBlockers(vec![(
+      Collider::convex_from_path(&Path::rect(Vec2::splat(0.4))),
+      Transform::from_translation(position.extend(-1.0)),
+    )])
+  }
+
+  #[test]
+  fn sweep_stops_on_the_near_side_of_an_obstacle() {
+    let blockers = pillar_at(Vec2::ZERO);
+    let player = Collider::Circle { radius: 0.25 };
+    // Landing just past the pillar's center would push the player out its far side.
+    for to in [Vec2::new(0.1, 0.), Vec2::new(1.5, 0.)] {
+      let stopped = blockers.sweep(&player, Vec2::new(-2., 0.), to);
+      assert!(
+        stopped.abs_diff_eq(Vec2::new(-0.65, 0.), 1e-4),
+        "swept to {:?}, stopped at {:?}",
+        to,
+        stopped
+      );
+    }
+  }
+
+  #[test]
+  fn sweep_reaches_the_target_when_nothing_is_in_the_way() {
+    let blockers = pillar_at(Vec2::new(0., 2.));
+    let player = Collider::Circle { radius: 0.25 };
+    assert_eq!(
+      blockers.sweep(&player, Vec2::new(-2., 0.), Vec2::new(2., 0.)),
+      Vec2::new(2., 0.)
+    );
+  }
+}
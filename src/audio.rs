@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use bevy::{audio::Volume, prelude::*};
+use rand::seq::SliceRandom;
+
+use crate::{
+  damage::{DamageArea, DamageSource, ResolvedDamageEvent},
+  pickups::BombPickupEvent,
+  playing::{EnemyKilledEvent, EnemySpawnTimer, Player, SwapEvent},
+  settings::Settings,
+  sfx::{synthesize_loop_wav, synthesize_wav, Note, SfxParams, Waveform},
+  AppState,
+};
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(Startup, load_sounds)
+      .add_systems(OnEnter(AppState::Playing), start_music)
+      .add_systems(OnEnter(AppState::GameOver), game_over_sfx)
+      .add_systems(
+        Update,
+        (
+          (
+            swap_sfx,
+            damage_area_sfx,
+            bomb_pickup_sfx,
+            hit_sfx,
+            death_sfx,
+          ),
+          adapt_music,
+        )
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+// Spawn rates, in enemies per second, where each music layer starts and finishes fading in.
+const MUSIC_LAYERS: [(f32, f32); 3] = [(0., 0.), (1.5, 2.5), (3., 4.5)];
+// How quickly music layers follow the spawn rate, as a fraction of full volume per second.
+const MUSIC_FADE_PER_SEC: f32 = 0.25;
+// 120 beats per minute, four bars of four.
+const MUSIC_BEAT_SECS: f32 = 0.5;
+const MUSIC_LOOP_BEATS: usize = 16;
+// One chord per bar, as the root in semitones from A2 and the third above it: Am, F, C, G.
+const MUSIC_CHORDS: [(i32, i32); 4] = [(0, 3), (-4, 4), (3, 4), (-2, 4)];
+// Slightly different takes on each sound effect, picked from at random each time one plays.
+const SFX_VARIANTS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Sfx {
+  Swap,
+  Dash,
+  Bomb,
+  EnemyHit,
+  EnemyDeath,
+  PlayerDamage,
+  GameOver,
+}
+
+impl Sfx {
+  const ALL: [Sfx; 7] = [
+    Sfx::Swap,
+    Sfx::Dash,
+    Sfx::Bomb,
+    Sfx::EnemyHit,
+    Sfx::EnemyDeath,
+    Sfx::PlayerDamage,
+    Sfx::GameOver,
+  ];
+
+  fn preset(&self) -> SfxParams {
+    let sound =
+      |waveform, start_hz, end_hz, (attack_secs, sustain_secs, decay_secs), volume| SfxParams {
+        waveform,
+        start_hz,
+        end_hz,
+        attack_secs,
+        sustain_secs,
+        decay_secs,
+        volume,
+      };
+    let square = Waveform::Square { duty: 0.5 };
+    match self {
+      // A quick rising blip.
+      Sfx::Swap => sound(Waveform::Sine, 600., 1200., (0.005, 0.03, 0.08), 0.5),
+      // A whoosh, falling away.
+      Sfx::Dash => sound(Waveform::Noise, 4000., 800., (0.01, 0.05, 0.15), 0.4),
+      // A low rumble that takes a while to die down.
+      Sfx::Bomb => sound(Waveform::Noise, 900., 60., (0., 0.1, 0.5), 0.8),
+      Sfx::EnemyHit => sound(
+        Waveform::Square { duty: 0.25 },
+        400.,
+        250.,
+        (0., 0.02, 0.05),
+        0.2,
+      ),
+      Sfx::EnemyDeath => sound(Waveform::Saw, 500., 80., (0., 0.05, 0.2), 0.35),
+      Sfx::PlayerDamage => sound(square, 220., 110., (0., 0.08, 0.15), 0.45),
+      Sfx::GameOver => sound(square, 440., 55., (0.01, 0.4, 0.6), 0.4),
+    }
+  }
+}
+
+// The notes of one music layer, from the quietest times up to the busiest.
+fn music_layer(index: usize) -> Vec<Note> {
+  let hz = |semitones: i32| 110. * 2_f32.powf(semitones as f32 / 12.);
+  let note = |beat: f32, waveform, hz, (attack_secs, sustain_secs, decay_secs), volume| Note {
+    start_secs: beat * MUSIC_BEAT_SECS,
+    params: SfxParams {
+      waveform,
+      start_hz: hz,
+      end_hz: hz,
+      attack_secs,
+      sustain_secs,
+      decay_secs,
+      volume,
+    },
+  };
+  let chord = |beat: usize| MUSIC_CHORDS[beat / 4 % MUSIC_CHORDS.len()];
+
+  match index {
+    // A bass note on every beat.
+    0 => (0..MUSIC_LOOP_BEATS)
+      .map(|beat| {
+        let (root, _) = chord(beat);
+        let square = Waveform::Square { duty: 0.25 };
+        note(beat as f32, square, hz(root), (0.01, 0.2, 0.2), 0.25)
+      })
+      .collect(),
+    // Hi-hats on the off beats.
+    1 => (0..MUSIC_LOOP_BEATS)
+      .map(|beat| {
+        let hat = (0., 0.01, 0.05);
+        note(beat as f32 + 0.5, Waveform::Noise, 7000., hat, 0.12)
+      })
+      .collect(),
+    // Arpeggios two octaves up, four notes to a beat.
+    _ => (0..MUSIC_LOOP_BEATS * 4)
+      .map(|step| {
+        let (root, third) = chord(step / 4);
+        let interval = [0, third, 7, 12][step % 4];
+        let pluck = (0.005, 0.05, 0.06);
+        note(
+          step as f32 / 4.,
+          Waveform::Sine,
+          hz(root + interval + 24),
+          pluck,
+          0.12,
+        )
+      })
+      .collect(),
+  }
+}
+
+#[derive(Resource, Debug)]
+struct Sounds {
+  effects: HashMap<Sfx, Vec<Handle<AudioSource>>>,
+  // Loops of the same length, layered on top of each other as things get busier.
+  music_layers: Vec<Handle<AudioSource>>,
+}
+
+// How loud a music layer currently is, before the volume settings are applied.
+#[derive(Component, Debug)]
+struct MusicLayer {
+  index: usize,
+  level: f32,
+}
+
+// Sound effects and music are synthesized rather than loaded, so there are no files to ship for
+// them.
+fn load_sounds(mut commands: Commands, mut audio_sources: ResMut<Assets<AudioSource>>) {
+  let mut rng = rand::thread_rng();
+  let mut variants = |sfx: Sfx| -> Vec<Handle<AudioSource>> {
+    (0..SFX_VARIANTS)
+      .map(|_| {
+        let wav = synthesize_wav(&sfx.preset().vary(&mut rng), &mut rng);
+        audio_sources.add(AudioSource { bytes: wav.into() })
+      })
+      .collect()
+  };
+  commands.insert_resource(Sounds {
+    effects: Sfx::ALL
+      .into_iter()
+      .map(|sfx| (sfx, variants(sfx)))
+      .collect(),
+    music_layers: (0..MUSIC_LAYERS.len())
+      .map(|index| {
+        let loop_secs = MUSIC_LOOP_BEATS as f32 * MUSIC_BEAT_SECS;
+        let wav = synthesize_loop_wav(&music_layer(index), loop_secs, &mut rng);
+        audio_sources.add(AudioSource { bytes: wav.into() })
+      })
+      .collect(),
+  });
+}
+
+fn play(commands: &mut Commands, sounds: &Sounds, settings: &Settings, sfx: Sfx) {
+  let volume = settings.volume.sfx();
+  if volume <= 0. {
+    return;
+  }
+  let Some(variant) = sounds.effects[&sfx].choose(&mut rand::thread_rng()) else {
+    return;
+  };
+  commands.spawn((
+    AudioPlayer::new(variant.clone()),
+    PlaybackSettings::DESPAWN.with_volume(Volume::new(volume)),
+  ));
+}
+
+fn swap_sfx(
+  mut commands: Commands,
+  mut swap_events: EventReader<SwapEvent>,
+  sounds: Res<Sounds>,
+  settings: Res<Settings>,
+) {
+  if swap_events.read().count() > 0 {
+    play(&mut commands, &sounds, &settings, Sfx::Swap);
+  }
+}
+
+// Dashes and bomb blasts both land as damage areas, so they sound off as the areas appear.
+fn damage_area_sfx(
+  mut commands: Commands,
+  areas: Query<&DamageArea, Added<DamageArea>>,
+  sounds: Res<Sounds>,
+  settings: Res<Settings>,
+) {
+  for (source, sfx) in [
+    (DamageSource::Dash, Sfx::Dash),
+    (DamageSource::Bomb, Sfx::Bomb),
+  ] {
+    if areas.iter().any(|area| area.source == source) {
+      play(&mut commands, &sounds, &settings, sfx);
+    }
+  }
+}
+
+// The bomb pickup clears the screen without a damage area, so it sounds off on its own.
+fn bomb_pickup_sfx(
+  mut commands: Commands,
+  mut bomb_events: EventReader<BombPickupEvent>,
+  sounds: Res<Sounds>,
+  settings: Res<Settings>,
+) {
+  if bomb_events.read().count() > 0 {
+    play(&mut commands, &sounds, &settings, Sfx::Bomb);
+  }
+}
+
+// At most one of each per frame, so a bomb clearing the screen doesn't deafen anyone.
+fn hit_sfx(
+  mut commands: Commands,
+  mut resolved_events: EventReader<ResolvedDamageEvent>,
+  players: Query<(), With<Player>>,
+  sounds: Res<Sounds>,
+  settings: Res<Settings>,
+) {
+  let (player_hits, enemy_hits): (Vec<_>, Vec<_>) = resolved_events
+    .read()
+    .partition(|event| players.contains(event.target));
+  if !player_hits.is_empty() {
+    play(&mut commands, &sounds, &settings, Sfx::PlayerDamage);
+  }
+  if !enemy_hits.is_empty() {
+    play(&mut commands, &sounds, &settings, Sfx::EnemyHit);
+  }
+}
+
+fn death_sfx(
+  mut commands: Commands,
+  mut killed_events: EventReader<EnemyKilledEvent>,
+  sounds: Res<Sounds>,
+  settings: Res<Settings>,
+) {
+  if killed_events.read().count() > 0 {
+    play(&mut commands, &sounds, &settings, Sfx::EnemyDeath);
+  }
+}
+
+fn game_over_sfx(mut commands: Commands, sounds: Res<Sounds>, settings: Res<Settings>) {
+  play(&mut commands, &sounds, &settings, Sfx::GameOver);
+}
+
+// Every layer starts together and silent, so they stay in sync however late each one fades in.
+fn start_music(mut commands: Commands, sounds: Res<Sounds>) {
+  for (index, layer) in sounds.music_layers.iter().enumerate() {
+    commands.spawn((
+      AudioPlayer::new(layer.clone()),
+      PlaybackSettings::LOOP.with_volume(Volume::new(0.)),
+      MusicLayer { index, level: 0. },
+      StateScoped(AppState::Playing),
+    ));
+  }
+}
+
+// Brings layers in as the spawn rate climbs.
+fn adapt_music(
+  mut layers: Query<(&mut MusicLayer, &AudioSink)>,
+  spawn_timer: Res<EnemySpawnTimer>,
+  settings: Res<Settings>,
+  time: Res<Time<Real>>,
+) {
+  let intensity = spawn_timer.enemies_per_sec();
+  let max_step = MUSIC_FADE_PER_SEC * time.delta_secs();
+  for (mut layer, sink) in layers.iter_mut() {
+    let (start, full) = MUSIC_LAYERS[layer.index];
+    let target = match full > start {
+      true => ((intensity - start) / (full - start)).clamp(0., 1.),
+      false => 1.,
+    };
+    layer.level += (target - layer.level).clamp(-max_step, max_step);
+    sink.set_volume(layer.level * settings.volume.music());
+  }
+}
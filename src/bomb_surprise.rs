@@ -1,32 +1,31 @@
 use bevy::prelude::*;
 
 use crate::{
-  damage::{ApplyDamageSet, DamageArea},
-  playing::{MovesStuffSet, Player, Reticle},
-  AppState, MouseControlled,
+  collision::{Collider, CollisionLayer, DetectCollisionsSet},
+  damage::{DamageArea, DamageSource},
+  playing::{MovesStuffSet, SwapEvent},
+  upgrades::Upgrades,
+  AppState, GameMode,
 };
 
 pub struct BombSurprisePlugin;
 
+pub const BOMB_HALF_SIZE: f32 = 1.5;
+pub const BOMB_DELAY_SECS: f32 = 0.2;
+
 impl Plugin for BombSurprisePlugin {
   fn build(&self, app: &mut App) {
-    app
-      .add_systems(OnEnter(AppState::Playing), init_resources)
-      .add_systems(
-        Update,
-        (bomb_swap.in_set(MovesStuffSet), boom.before(ApplyDamageSet))
-          .run_if(in_state(AppState::Playing)),
-      );
+    app.insert_resource(GameMode::BombSurprise).add_systems(
+      Update,
+      (
+        bomb_swap.after(MovesStuffSet),
+        boom.before(DetectCollisionsSet),
+      )
+        .run_if(in_state(AppState::Playing)),
+    );
   }
 }
 
-#[derive(Resource)]
-struct SwapTimer(Timer);
-
-fn init_resources(mut commands: Commands) {
-  commands.insert_resource(SwapTimer(Timer::from_seconds(1.0, TimerMode::Repeating)));
-}
-
 #[derive(Component, Debug)]
 struct Bomb {
   damage: u32,
@@ -34,45 +33,23 @@ struct Bomb {
   delay: Timer,
 }
 
+// Leave a bomb behind whenever the player swaps. It explodes after a brief delay of its own,
+// damaging enemies in the area.
 fn bomb_swap(
   mut commands: Commands,
-  mut timer: ResMut<SwapTimer>,
-  time: Res<Time>,
-  mut player: Query<(&mut Transform, &mut MouseControlled), (With<Player>, Without<Reticle>)>,
-  mut reticle: Query<(&mut Transform, &mut MouseControlled), (With<Reticle>, Without<Player>)>,
+  mut swap_events: EventReader<SwapEvent>,
+  upgrades: Res<Upgrades>,
 ) {
-  // On a timer, swap the player and reticle, and spawn a bomb at the player's previous position.
-  // The bomb explodes after a brief delay of its own, damaging enemies in the area.
-
-  if !timer.0.tick(time.delta()).just_finished() {
-    return;
+  for SwapEvent { from, to: _ } in swap_events.read() {
+    commands.spawn((
+      Transform::from_translation(from.extend(0.0)),
+      Bomb {
+        damage: 10,
+        half_size: Vec2::splat(upgrades.bomb_half_size()),
+        delay: Timer::from_seconds(upgrades.bomb_delay_secs(), TimerMode::Once),
+      },
+    ));
   }
-
-  // Spawn bomb at player's current position
-  commands.spawn((
-    Transform::from_translation(player.single().0.translation),
-    Bomb {
-      damage: 10,
-      half_size: Vec2::new(1.5, 1.5),
-      delay: Timer::from_seconds(0.2, TimerMode::Once),
-    },
-  ));
-
-  // Swap player and reticle
-  let (mut player_transform, mut player_control) = player.single_mut();
-  let (mut reticle_transform, mut reticle_control) = reticle.single_mut();
-
-  std::mem::swap(
-    &mut player_transform.translation,
-    &mut reticle_transform.translation,
-  );
-  std::mem::swap(
-    &mut player_transform.rotation,
-    &mut reticle_transform.rotation,
-  );
-
-  std::mem::swap(&mut player_control.id, &mut reticle_control.id);
-  std::mem::swap(&mut player_control.hand, &mut reticle_control.hand);
 }
 
 fn boom(
@@ -91,7 +68,12 @@ fn boom(
       DamageArea {
         damage: bomb.damage,
         half_size: bomb.half_size,
+        source: DamageSource::Bomb,
+      },
+      Collider::Obb {
+        half_size: bomb.half_size,
       },
+      CollisionLayer::DamageArea,
     ));
 
     commands.entity(entity).despawn_recursive();
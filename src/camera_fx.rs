@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+  damage::{DamageSource, ResolvedDamageEvent},
+  playing::{EnemyKilledEvent, Player, SwapEvent},
+  settings::Settings,
+  AppState,
+};
+
+pub struct CameraFxPlugin;
+
+impl Plugin for CameraFxPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Trauma>()
+      .init_resource::<HitStop>()
+      .add_systems(OnExit(AppState::Playing), end_hit_stop)
+      .add_systems(
+        Update,
+        (
+          add_camera_fx,
+          (add_trauma, start_hit_stop, punch_zoom),
+          (tick_hit_stop, apply_camera_fx),
+        )
+          .chain(),
+      );
+  }
+}
+
+// Offset at full trauma, in world units.
+const MAX_SHAKE_OFFSET: f32 = 0.3;
+// Trauma lost per second.
+const TRAUMA_DECAY: f32 = 1.2;
+const PLAYER_HIT_TRAUMA: f32 = 0.5;
+const KILL_TRAUMA: f32 = 0.08;
+
+// Dash kills in a single frame needed to freeze the action for a moment.
+const HIT_STOP_MIN_KILLS: usize = 3;
+const MAX_HIT_STOP_SECS: f32 = 0.12;
+
+// How far a swap zooms in, as a fraction of the view.
+const ZOOM_PUNCH: f32 = 0.04;
+// Fraction of the punch left after a second.
+const ZOOM_PUNCH_RETURN: f32 = 0.001;
+
+// Shake builds up with each hit and wears off over time. Shake scales with its square, so small
+// bumps barely register while big ones really rattle.
+#[derive(Resource, Debug, Default)]
+struct Trauma(f32);
+
+// Real time left to hold gameplay still, if a hit-stop is going.
+#[derive(Resource, Debug, Default)]
+struct HitStop(Option<Timer>);
+
+// Where the camera sits with no effects applied.
+#[derive(Component, Debug)]
+struct CameraFx {
+  base_translation: Vec3,
+  base_scale: f32,
+  zoom_punch: f32,
+}
+
+fn add_camera_fx(
+  mut commands: Commands,
+  cameras: Query<(Entity, &Transform, &OrthographicProjection), Added<Camera2d>>,
+) {
+  for (entity, transform, projection) in cameras.iter() {
+    commands.entity(entity).insert(CameraFx {
+      base_translation: transform.translation,
+      base_scale: projection.scale,
+      zoom_punch: 0.,
+    });
+  }
+}
+
+fn add_trauma(
+  mut trauma: ResMut<Trauma>,
+  mut resolved_events: EventReader<ResolvedDamageEvent>,
+  mut killed_events: EventReader<EnemyKilledEvent>,
+  players: Query<(), With<Player>>,
+) {
+  for event in resolved_events.read() {
+    if players.contains(event.target) {
+      trauma.0 += PLAYER_HIT_TRAUMA;
+    }
+  }
+  trauma.0 += KILL_TRAUMA * killed_events.read().count() as f32;
+  trauma.0 = trauma.0.min(1.);
+}
+
+// Gameplay time is slowed to a stop rather than paused, so it doesn't fight with drafts pausing it.
+fn start_hit_stop(
+  mut hit_stop: ResMut<HitStop>,
+  mut killed_events: EventReader<EnemyKilledEvent>,
+  mut time: ResMut<Time<Virtual>>,
+  settings: Res<Settings>,
+) {
+  let dash_kills = killed_events
+    .read()
+    .filter(|event| event.source == Some(DamageSource::Dash))
+    .count();
+  let duration = MAX_HIT_STOP_SECS * settings.hit_stop.strength();
+  if dash_kills < HIT_STOP_MIN_KILLS || duration <= 0. {
+    return;
+  }
+
+  hit_stop.0 = Some(Timer::from_seconds(duration, TimerMode::Once));
+  time.set_relative_speed(0.);
+}
+
+// Counted in real time, since gameplay time is stopped.
+fn tick_hit_stop(
+  mut hit_stop: ResMut<HitStop>,
+  mut time: ResMut<Time<Virtual>>,
+  real_time: Res<Time<Real>>,
+) {
+  let Some(timer) = hit_stop.0.as_mut() else {
+    return;
+  };
+  if timer.tick(real_time.delta()).finished() {
+    hit_stop.0 = None;
+    time.set_relative_speed(1.);
+  }
+}
+
+fn end_hit_stop(mut hit_stop: ResMut<HitStop>, mut time: ResMut<Time<Virtual>>) {
+  hit_stop.0 = None;
+  time.set_relative_speed(1.);
+}
+
+fn punch_zoom(mut swap_events: EventReader<SwapEvent>, mut cameras: Query<&mut CameraFx>) {
+  if swap_events.read().count() == 0 {
+    return;
+  }
+  for mut camera_fx in cameras.iter_mut() {
+    camera_fx.zoom_punch = 1.;
+  }
+}
+
+// Runs on real time so the camera settles even while gameplay is paused or stopped.
+fn apply_camera_fx(
+  mut trauma: ResMut<Trauma>,
+  mut cameras: Query<(&mut CameraFx, &mut Transform, &mut OrthographicProjection)>,
+  settings: Res<Settings>,
+  time: Res<Time<Real>>,
+) {
+  let dt = time.delta_secs();
+  trauma.0 = (trauma.0 - TRAUMA_DECAY * dt).max(0.);
+
+  let mut rng = rand::thread_rng();
+  let shake = trauma.0 * trauma.0 * MAX_SHAKE_OFFSET * settings.screen_shake.strength();
+  for (mut camera_fx, mut transform, mut projection) in cameras.iter_mut() {
+    let offset = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * shake;
+    transform.translation = camera_fx.base_translation + offset.extend(0.);
+
+    camera_fx.zoom_punch *= ZOOM_PUNCH_RETURN.powf(dt);
+    let zoom = 1. - ZOOM_PUNCH * camera_fx.zoom_punch * settings.zoom_punch.strength();
+    projection.scale = camera_fx.base_scale * zoom;
+  }
+}
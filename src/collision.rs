@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+  damage::ApplyDamageSet, path::Path, playing::MovesStuffSet, spatial_grid::SpatialGrid,
+};
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_event::<CollisionEvent>()
+      .insert_resource(SpatialIndex(SpatialGrid::new(SPATIAL_CELL_SIZE)))
+      .configure_sets(
+        Update,
+        DetectCollisionsSet
+          .after(MovesStuffSet)
+          .before(ApplyDamageSet),
+      )
+      .add_systems(
+        Update,
+        (rebuild_spatial_index, detect_collisions)
+          .chain()
+          .in_set(DetectCollisionsSet),
+      );
+  }
+}
+
+// Sends this frame's CollisionEvents. Anything that spawns colliders for this frame runs before it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DetectCollisionsSet;
+
+// Roughly the size of an enemy, so most cells hold only a handful of colliders.
+const SPATIAL_CELL_SIZE: f32 = 1.0;
+
+// Every collider's bounding box as of this frame's collision detection.
+#[derive(Resource)]
+pub struct SpatialIndex(SpatialGrid<(Entity, CollisionLayer)>);
+
+impl SpatialIndex {
+  // Colliders on the given layer whose bounding boxes overlap the rect.
+  pub fn query_rect(&self, rect: Rect, layer: CollisionLayer) -> impl Iterator<Item = Entity> + '_ {
+    self
+      .0
+      .query_rect(rect)
+      .filter(move |(_, found_layer)| *found_layer == layer)
+      .map(|(entity, _)| entity)
+  }
+}
+
+// Collision shape, in the entity's local space.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub enum Collider {
+  Circle { radius: f32 },
+  // Oriented bounding box, centered on the entity.
+  Obb { half_size: Vec2 },
+  // Must be convex. Winding order doesn't matter. With no points it has no bounds and never
+  // collides.
+  Convex { points: Vec<Vec2> },
+}
+
+impl Collider {
+  // Uses the path's vertices as the hull, so the path must describe a convex polygon.
+  pub fn convex_from_path(path: &Path) -> Self {
+    Collider::Convex {
+      points: path.vertices.clone(),
+    }
+  }
+
+  // For checking shapes outside of collision detection, like previewing what an attack would hit.
+  pub fn overlaps(
+    &self,
+    transform: &Transform,
+    other: &Collider,
+    other_transform: &Transform,
+  ) -> bool {
+    self
+      .penetration(transform, other, other_transform)
+      .is_some()
+  }
+
+  // The shortest move that would take this collider out of the other one, if they overlap.
+  pub fn penetration(
+    &self,
+    transform: &Transform,
+    other: &Collider,
+    other_transform: &Transform,
+  ) -> Option<Vec2> {
+    self
+      .to_world(transform)
+      .penetration(&other.to_world(other_transform))
+  }
+
+  pub fn aabb(&self, transform: &Transform) -> Rect {
+    self.to_world(transform).aabb()
+  }
+
+  fn to_world(&self, transform: &Transform) -> WorldShape {
+    let to_world = |point: Vec2| transform.transform_point(point.extend(0.)).xy();
+    match self {
+      Collider::Circle { radius } => WorldShape::Circle {
+        center: transform.translation.xy(),
+        radius: radius * transform.scale.x.abs().max(transform.scale.y.abs()),
+      },
+      Collider::Obb { half_size } => WorldShape::Polygon(
+        [
+          Vec2::new(-half_size.x, -half_size.y),
+          Vec2::new(half_size.x, -half_size.y),
+          Vec2::new(half_size.x, half_size.y),
+          Vec2::new(-half_size.x, half_size.y),
+        ]
+        .into_iter()
+        .map(to_world)
+        .collect(),
+      ),
+      Collider::Convex { points } => {
+        WorldShape::Polygon(points.iter().copied().map(to_world).collect())
+      }
+    }
+  }
+}
+
+// Which kind of thing a collider belongs to. Only some pairs of layers are checked.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollisionLayer {
+  Player,
+  Enemy,
+  DamageArea,
+  Pickup,
+  // Blocks the player, and enemies bounce off it.
+  Obstacle,
+  // Blocks only the player.
+  Wall,
+  // Hurts the player while they're in it.
+  Hazard,
+  // Hits enemies, and is stopped by obstacles.
+  Projectile,
+}
+
+impl CollisionLayer {
+  fn interacts_with(self, other: CollisionLayer) -> bool {
+    use CollisionLayer::*;
+    matches!(
+      (self, other),
+      (Player, Enemy)
+        | (Enemy, Player)
+        | (DamageArea, Enemy)
+        | (Enemy, DamageArea)
+        | (Player, Pickup)
+        | (Pickup, Player)
+        | (Player, Obstacle)
+        | (Obstacle, Player)
+        | (Enemy, Obstacle)
+        | (Obstacle, Enemy)
+        | (Player, Wall)
+        | (Wall, Player)
+        | (Player, Hazard)
+        | (Hazard, Player)
+        | (Projectile, Enemy)
+        | (Enemy, Projectile)
+        | (Projectile, Obstacle)
+        | (Obstacle, Projectile)
+    )
+  }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CollisionEvent {
+  entities: [Entity; 2],
+  layers: [CollisionLayer; 2],
+}
+
+impl CollisionEvent {
+  // The colliding entities in the order of the requested layers, if this collision is between them.
+  pub fn between(&self, first: CollisionLayer, second: CollisionLayer) -> Option<(Entity, Entity)> {
+    match self.layers {
+      [a, b] if a == first && b == second => Some((self.entities[0], self.entities[1])),
+      [a, b] if a == second && b == first => Some((self.entities[1], self.entities[0])),
+      _ => None,
+    }
+  }
+}
+
+fn rebuild_spatial_index(
+  mut index: ResMut<SpatialIndex>,
+  colliders: Query<(Entity, &Collider, &CollisionLayer, &Transform)>,
+) {
+  index.0.clear();
+  for (entity, collider, layer, transform) in colliders.iter() {
+    index
+      .0
+      .insert((entity, *layer), collider.to_world(transform).aabb());
+  }
+}
+
+fn detect_collisions(
+  index: Res<SpatialIndex>,
+  colliders: Query<(Entity, &Collider, &CollisionLayer, &Transform)>,
+  mut collision_events: EventWriter<CollisionEvent>,
+) {
+  let shapes = colliders
+    .iter()
+    .map(|(entity, collider, _, transform)| (entity, collider.to_world(transform)))
+    .collect::<HashMap<_, _>>();
+
+  for (entity_a, _, layer_a, _) in colliders.iter() {
+    let shape_a = &shapes[&entity_a];
+    for (entity_b, layer_b) in index.0.query_rect(shape_a.aabb()) {
+      // Each pair turns up from both sides; only handle it once.
+      if entity_a >= entity_b || !layer_a.interacts_with(layer_b) {
+        continue;
+      }
+      if shape_a.penetration(&shapes[&entity_b]).is_some() {
+        collision_events.send(CollisionEvent {
+          entities: [entity_a, entity_b],
+          layers: [*layer_a, layer_b],
+        });
+      }
+    }
+  }
+}
+
+enum WorldShape {
+  Circle { center: Vec2, radius: f32 },
+  Polygon(Vec<Vec2>),
+}
+
+impl WorldShape {
+  fn aabb(&self) -> Rect {
+    match self {
+      WorldShape::Circle { center, radius } => {
+        Rect::from_center_half_size(*center, Vec2::splat(*radius))
+      }
+      WorldShape::Polygon(points) => points.iter().fold(
+        Rect {
+          min: Vec2::INFINITY,
+          max: Vec2::NEG_INFINITY,
+        },
+        |aabb, point| aabb.union_point(*point),
+      ),
+    }
+  }
+
+  // Interval covered by the shape when projected onto an axis.
+  fn project(&self, axis: Vec2) -> (f32, f32) {
+    match self {
+      WorldShape::Circle { center, radius } => {
+        let projected = center.dot(axis);
+        (projected - radius, projected + radius)
+      }
+      WorldShape::Polygon(points) => points.iter().map(|point| point.dot(axis)).fold(
+        (f32::INFINITY, f32::NEG_INFINITY),
+        |(min, max), projected| (min.min(projected), max.max(projected)),
+      ),
+    }
+  }
+
+  // Separating axes to test against `other`.
+  fn axes(&self, other: &WorldShape) -> Vec<Vec2> {
+    match self {
+      WorldShape::Polygon(points) => (0..points.len())
+        .filter_map(|i| {
+          let edge = points[(i + 1) % points.len()] - points[i];
+          edge.perp().try_normalize()
+        })
+        .collect(),
+      // A circle's only useful axis points at the nearest feature of the other shape.
+      WorldShape::Circle { center, .. } => {
+        let nearest = match other {
+          WorldShape::Circle { center, .. } => *center,
+          WorldShape::Polygon(points) => points
+            .iter()
+            .copied()
+            .min_by(|a, b| {
+              a.distance_squared(*center)
+                .total_cmp(&b.distance_squared(*center))
+            })
+            .unwrap_or(*center),
+        };
+        (nearest - *center).try_normalize().into_iter().collect()
+      }
+    }
+  }
+
+  // Separating axis test. Returns the minimum translation vector that moves self out of other.
+  fn penetration(&self, other: &WorldShape) -> Option<Vec2> {
+    let mut axes = self.axes(other);
+    axes.extend(other.axes(self));
+    if axes.is_empty() {
+      // Two circles sharing a center.
+      axes.push(Vec2::Y);
+    }
+
+    let mut shortest: Option<Vec2> = None;
+    for axis in axes {
+      let (min_a, max_a) = self.project(axis);
+      let (min_b, max_b) = other.project(axis);
+      // How far self would have to move along +axis or -axis to clear other.
+      let push_forward = max_b - min_a;
+      let push_back = max_a - min_b;
+      if push_forward <= 0. || push_back <= 0. {
+        return None;
+      }
+
+      let push = if push_forward < push_back {
+        axis * push_forward
+      } else {
+        -axis * push_back
+      };
+      if shortest.is_none_or(|shortest| push.length_squared() < shortest.length_squared()) {
+        shortest = Some(push);
+      }
+    }
+    shortest
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::f32::consts::FRAC_PI_4;
+
+  use super::*;
+
+  fn at(x: f32, y: f32) -> Transform {
+    Transform::from_xyz(x, y, 0.)
+  }
+
+  fn circle(radius: f32) -> Collider {
+    Collider::Circle { radius }
+  }
+
+  fn square(half_size: f32) -> Collider {
+    Collider::Obb {
+      half_size: Vec2::splat(half_size),
+    }
+  }
+
+  fn assert_near(actual: Vec2, expected: Vec2) {
+    assert!(
+      actual.abs_diff_eq(expected, 1e-4),
+      "expected {:?}, got {:?}",
+      expected,
+      actual
+    );
+  }
+
+  #[test]
+  fn overlapping_circles_push_apart_along_their_centers() {
+    let push = circle(1.)
+      .penetration(&at(0., 0.), &circle(1.), &at(1.5, 0.))
+      .unwrap();
+    assert_near(push, Vec2::new(-0.5, 0.));
+  }
+
+  #[test]
+  fn touching_shapes_do_not_overlap() {
+    assert_eq!(
+      circle(1.).penetration(&at(0., 0.), &circle(1.), &at(2., 0.)),
+      None
+    );
+    assert_eq!(
+      square(1.).penetration(&at(0., 0.), &square(1.), &at(2., 0.)),
+      None
+    );
+    assert_eq!(
+      circle(1.).penetration(&at(0., 0.), &square(1.), &at(2., 0.)),
+      None
+    );
+  }
+
+  #[test]
+  fn concentric_circles_still_get_a_push() {
+    let push = circle(1.)
+      .penetration(&at(3., 3.), &circle(0.5), &at(3., 3.))
+      .unwrap();
+    assert_eq!(push.length(), 1.5);
+  }
+
+  #[test]
+  fn circle_near_a_box_corner_uses_the_corner_axis() {
+    // Inside the box's bounds along both edge normals, but past the corner diagonally.
+    let transform = at(1.6, 1.6);
+    assert_eq!(
+      circle(0.8).penetration(&transform, &square(1.), &at(0., 0.)),
+      None
+    );
+    assert!(!square(1.).overlaps(&at(0., 0.), &circle(0.8), &transform));
+
+    // Closer in, the corner axis gives the shortest way out.
+    let push = circle(0.8)
+      .penetration(&at(1.5, 1.5), &square(1.), &at(0., 0.))
+      .unwrap();
+    let expected_depth = 0.8 - Vec2::splat(0.5).length();
+    assert_near(push, Vec2::splat(1.).normalize() * expected_depth);
+  }
+
+  #[test]
+  fn circle_against_a_box_face_pushes_straight_out() {
+    let push = circle(0.5)
+      .penetration(&at(0., 1.25), &square(1.), &at(0., 0.))
+      .unwrap();
+    assert_near(push, Vec2::new(0., 0.25));
+  }
+
+  #[test]
+  fn rotated_box_against_convex_uses_the_box_edges() {
+    let diamond = Transform::from_rotation(Quat::from_rotation_z(FRAC_PI_4));
+    let triangle = Collider::Convex {
+      points: vec![Vec2::new(1.2, 0.), Vec2::new(3., -1.), Vec2::new(3., 1.)],
+    };
+    // The diamond's corner reaches out to sqrt(2) along +x, past the triangle's tip at 1.2.
+    assert!(square(1.).overlaps(&diamond, &triangle, &at(0., 0.)));
+    // Unrotated, the box only reaches 1.
+    assert!(!square(1.).overlaps(&at(0., 0.), &triangle, &at(0., 0.)));
+
+    let push = square(1.)
+      .penetration(&diamond, &triangle, &at(0., 0.))
+      .unwrap();
+    // The shortest way out is back along one of the diamond's edge normals or the triangle's.
+    assert!(push.x < 0.);
+    let moved = diamond.with_translation(push.extend(0.) * 1.001);
+    assert!(!square(1.).overlaps(&moved, &triangle, &at(0., 0.)));
+  }
+
+  #[test]
+  fn convex_winding_does_not_matter() {
+    let points = vec![Vec2::new(0., 0.), Vec2::new(2., 0.), Vec2::new(1., 2.)];
+    let reversed = points.iter().rev().copied().collect();
+    let counterclockwise = Collider::Convex { points };
+    let clockwise = Collider::Convex { points: reversed };
+    let probe = at(1., -0.3);
+    assert_eq!(
+      circle(0.5).penetration(&probe, &counterclockwise, &at(0., 0.)),
+      circle(0.5).penetration(&probe, &clockwise, &at(0., 0.))
+    );
+  }
+
+  #[test]
+  fn empty_convex_never_collides() {
+    let empty = Collider::Convex { points: vec![] };
+    assert!(!circle(1.).overlaps(&at(0., 0.), &empty, &at(0., 0.)));
+  }
+
+  // Blocking and sweeping add the push to the mover's position and expect it to end up just
+  // clear of the thing it hit, on the side it came from.
+  #[test]
+  fn push_moves_the_first_shape_out_on_the_near_side() {
+    let wall = Collider::Obb {
+      half_size: Vec2::new(0.1, 2.),
+    };
+    let player = circle(0.3);
+    let position = Vec2::new(-0.25, 0.5);
+    let push = player
+      .penetration(&at(position.x, position.y), &wall, &at(0., 0.))
+      .unwrap();
+    assert_near(push, Vec2::new(-0.15, 0.));
+
+    let cleared = position + push;
+    assert_near(cleared, Vec2::new(-0.4, 0.5));
+    assert_eq!(
+      player.penetration(&at(cleared.x - 1e-3, cleared.y), &wall, &at(0., 0.)),
+      None
+    );
+
+    // From the other side, the push flips.
+    let push = player
+      .penetration(&at(0.25, 0.5), &wall, &at(0., 0.))
+      .unwrap();
+    assert_near(push, Vec2::new(0.15, 0.));
+  }
+
+  #[test]
+  fn scale_grows_the_collider() {
+    let scaled = Transform::from_scale(Vec3::new(2., 1., 1.));
+    assert!(circle(1.).overlaps(&scaled, &circle(1.), &at(2.5, 0.)));
+    assert!(square(1.).overlaps(&scaled, &square(1.), &at(2.5, 0.)));
+    assert_eq!(
+      circle(1.).aabb(&scaled),
+      Rect::from_center_half_size(Vec2::ZERO, Vec2::splat(2.))
+    );
+  }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+  damage::ResolvedDamageEvent,
+  playing::{EnemyKilledEvent, Player, Score, SwapEvent},
+  AppState, EnableStateScopedResource,
+};
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .enable_state_scoped_resource::<Combo>(AppState::GameOver)
+      .add_systems(OnEnter(AppState::Playing), init_resources)
+      .add_systems(
+        // PostUpdate so that kills on the frame the player dies still score.
+        PostUpdate,
+        (
+          decay_combo,
+          score_kills,
+          heat_from_long_dashes,
+          break_combo_on_damage,
+        )
+          .chain()
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+const MAX_MULTIPLIER: u32 = 8;
+// Heat gained per kill. Every full point of heat adds one to the multiplier.
+const KILL_HEAT: f32 = 0.25;
+// Extra heat for each kill beyond the first from a single attack.
+const MULTI_KILL_HEAT: f32 = 0.5;
+// Swaps longer than this earn heat for every meter past it.
+const LONG_DASH_DISTANCE: f32 = 6.0;
+const LONG_DASH_HEAT_PER_METER: f32 = 0.1;
+// Heat holds steady for a moment after any gain, then drains.
+const DECAY_DELAY_SECS: f32 = 1.5;
+const DECAY_PER_SEC: f32 = 0.5;
+
+#[derive(Resource, Debug)]
+pub struct Combo {
+  heat: f32,
+  decay_delay: Timer,
+}
+
+impl Combo {
+  pub fn multiplier(&self) -> u32 {
+    1 + self.heat as u32
+  }
+
+  fn add_heat(&mut self, heat: f32) {
+    self.heat = (self.heat + heat).min((MAX_MULTIPLIER - 1) as f32);
+    self.decay_delay.reset();
+  }
+}
+
+fn init_resources(mut commands: Commands) {
+  commands.insert_resource(Combo {
+    heat: 0.,
+    decay_delay: Timer::from_seconds(DECAY_DELAY_SECS, TimerMode::Once),
+  });
+}
+
+fn decay_combo(mut combo: ResMut<Combo>, time: Res<Time>) {
+  if !combo.decay_delay.tick(time.delta()).finished() {
+    return;
+  }
+  combo.heat = (combo.heat - DECAY_PER_SEC * time.delta_secs()).max(0.);
+}
+
+fn score_kills(
+  mut combo: ResMut<Combo>,
+  mut score: ResMut<Score>,
+  mut killed_events: EventReader<EnemyKilledEvent>,
+) {
+  // Several kills from one attack make a multi-kill. Touching enemies isn't an attack.
+  let mut kills_by_attack: HashMap<Entity, u32> = HashMap::new();
+  for EnemyKilledEvent { attack, .. } in killed_events.read() {
+    score.0 += combo.multiplier() as i32;
+    combo.add_heat(KILL_HEAT);
+    if let Some(attack) = attack {
+      *kills_by_attack.entry(*attack).or_default() += 1;
+    }
+  }
+
+  for kills in kills_by_attack.into_values() {
+    if kills > 1 {
+      combo.add_heat(MULTI_KILL_HEAT * (kills - 1) as f32);
+    }
+  }
+}
+
+fn heat_from_long_dashes(mut combo: ResMut<Combo>, mut swap_events: EventReader<SwapEvent>) {
+  for SwapEvent { from, to } in swap_events.read() {
+    let extra_distance = from.distance(*to) - LONG_DASH_DISTANCE;
+    if extra_distance > 0. {
+      combo.add_heat(extra_distance * LONG_DASH_HEAT_PER_METER);
+    }
+  }
+}
+
+fn break_combo_on_damage(
+  mut combo: ResMut<Combo>,
+  mut damage_events: EventReader<ResolvedDamageEvent>,
+  player: Query<(), With<Player>>,
+) {
+  let player_hits = damage_events
+    .read()
+    .filter(|event| player.contains(event.target))
+    .count();
+  if player_hits > 0 {
+    combo.heat = 0.;
+  }
+}
@@ -1,79 +1,433 @@
+use std::{collections::HashMap, time::Duration};
+
 use bevy::prelude::*;
-use bevy_prototype_lyon::{prelude::*, shapes};
+use rand::Rng;
 
-use crate::playing::{Enemy, Player};
+use crate::{
+  arena::ArenaBlockers,
+  collision::{Collider, CollisionEvent, CollisionLayer},
+  path::Path,
+  pickups::Shield,
+  playing::{Enemy, MovesStuffSet, Player},
+  window_setup::PlayArea,
+  MOUSE_RADIUS,
+};
 
 pub struct DamagePlugin;
 
 impl Plugin for DamagePlugin {
   fn build(&self, app: &mut App) {
-    app.add_systems(
-      Update,
-      (
-        (contact_damage, damage_enemies_in_area).in_set(ApplyDamageSet),
-        damage_flicker,
-      ),
-    );
+    app
+      .init_resource::<CriticalHits>()
+      .add_event::<DamageEvent>()
+      .add_event::<ResolvedDamageEvent>()
+      .add_event::<DamageAreaResolvedEvent>()
+      .configure_sets(Update, DetectDamageSet.in_set(ApplyDamageSet))
+      .add_systems(Startup, build_damage_visuals)
+      .add_systems(
+        Update,
+        (
+          (contact_damage, damage_enemies_in_area).in_set(DetectDamageSet),
+          resolve_damage.in_set(ApplyDamageSet).after(DetectDamageSet),
+          spawn_crit_flashes.after(ApplyDamageSet),
+          tick_invulnerability,
+          flash_invulnerable_player,
+          apply_knockback.in_set(MovesStuffSet),
+          damage_flicker,
+        ),
+      );
   }
 }
 
+// Contact is resolved as discrete hits, with the player invulnerable for a while after each one.
+const CONTACT_DAMAGE: u32 = 5;
+const PLAYER_KNOCKBACK_SPEED: f32 = 10.0;
+const ENEMY_KNOCKBACK_SPEED: f32 = 6.0;
+// How quickly knockback velocity bleeds off, per second.
+const KNOCKBACK_DAMPING: f32 = 8.0;
+const INVULNERABLE_FLASHES_PER_SEC: f32 = 10.0;
+
+// Everything that changes hp. Systems that spawn damage dealers run before it, and systems that
+// display hp or react to hits run after it.
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ApplyDamageSet;
 
+// The part of ApplyDamageSet that works out who got hit and sends DamageEvents for it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DetectDamageSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageSource {
+  Contact,
+  Dash,
+  Bomb,
+  Shot,
+}
+
+impl DamageSource {
+  pub const ALL: [DamageSource; 4] = [
+    DamageSource::Contact,
+    DamageSource::Dash,
+    DamageSource::Bomb,
+    DamageSource::Shot,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      DamageSource::Contact => "Contact",
+      DamageSource::Dash => "Dash",
+      DamageSource::Bomb => "Bomb",
+      DamageSource::Shot => "Shot",
+    }
+  }
+
+  // Only damage dealt by the player can crit.
+  fn can_crit(&self) -> bool {
+    *self != DamageSource::Contact
+  }
+}
+
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct DamageArea {
   pub damage: u32,
   pub half_size: Vec2,
+  pub source: DamageSource,
+}
+
+// A hit before any modifiers are applied. Only resolve_damage should change hp.
+#[derive(Event, Debug, Clone)]
+pub struct DamageEvent {
+  pub target: Entity,
+  pub amount: u32,
+  pub source: DamageSource,
+  // The damage area, projectile or pickup that dealt the hit. None for touching things.
+  pub attack: Option<Entity>,
+  pub position: Vec2,
+}
+
+// A hit after modifiers, for the hp that was actually lost. Hits that did nothing aren't sent.
+#[derive(Event, Debug, Clone)]
+pub struct ResolvedDamageEvent {
+  pub target: Entity,
+  pub amount: u32,
+  pub position: Vec2,
+  pub crit: bool,
+}
+
+// Flat reduction applied to every hit taken.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Armor(pub u32);
+
+// Ignores all damage until the timer runs out.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Invulnerable(pub Timer);
+
+// Grants Invulnerable for this long whenever the entity loses hp.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct HitInvulnerability(pub Duration);
+
+// Velocity from being shoved, on top of whatever normally moves the entity. Decays over time.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Knockback(pub Vec2);
+
+// Off unless something raises the chance, so hits do what they say by default.
+#[derive(Resource, Debug)]
+pub struct CriticalHits {
+  pub chance: f32,
+  pub multiplier: u32,
+}
+
+impl Default for CriticalHits {
+  fn default() -> Self {
+    Self {
+      chance: 0.,
+      multiplier: 2,
+    }
+  }
+}
+
+// Meshes and materials for the flashes damage leaves behind, shared by all of them.
+#[derive(Resource)]
+struct DamageVisuals {
+  // A unit square, scaled to fit each damage area.
+  area: Handle<Mesh>,
+  area_material: Handle<ColorMaterial>,
+  crit: Handle<Mesh>,
+  crit_material: Handle<ColorMaterial>,
+}
+
+// Sent once per attack: for a damage area after it has been checked against every enemy, and for a
+// projectile once it's used up or gone.
+#[derive(Event, Debug)]
+pub struct DamageAreaResolvedEvent {
+  pub hits: u32,
+}
+
+fn build_damage_visuals(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+  let square = |half_size: Vec2| {
+    Path::rect(half_size)
+      .build_triangle_mesh()
+      .expect("rectangles are simple polygons")
+  };
+  commands.insert_resource(DamageVisuals {
+    area: meshes.add(square(Vec2::splat(0.5))),
+    area_material: materials.add(Color::srgba(1.0, 1.0, 1.0, 0.2)),
+    crit: meshes.add(square(Vec2::splat(0.15))),
+    crit_material: materials.add(Color::srgba(1.0, 1.0, 0.6, 0.8)),
+  });
 }
 
 fn damage_enemies_in_area(
   mut commands: Commands,
-  damage_areas: Query<(Entity, &Transform, &DamageArea)>,
-  mut enemies: Query<(&Transform, &mut Enemy)>,
+  visuals: Res<DamageVisuals>,
+  damage_areas: Query<(Entity, &DamageArea)>,
+  enemies: Query<&Transform, With<Enemy>>,
+  mut collision_events: EventReader<CollisionEvent>,
+  mut damage_events: EventWriter<DamageEvent>,
+  mut resolved_events: EventWriter<DamageAreaResolvedEvent>,
 ) {
-  for (entity, area_transform, area) in damage_areas.iter() {
-    let world_to_area = area_transform.compute_matrix().inverse();
-    for (enemy_transform, mut enemy) in enemies.iter_mut() {
-      let enemy_pos_in_area = world_to_area.transform_point3(enemy_transform.translation);
-
-      if enemy_pos_in_area.x > -area.half_size.x
-        && enemy_pos_in_area.x < area.half_size.x
-        && enemy_pos_in_area.y > -area.half_size.y
-        && enemy_pos_in_area.y < area.half_size.y
-      {
-        enemy.hp = enemy.hp.saturating_sub(area.damage);
+  let mut hits_by_area: HashMap<Entity, u32> = HashMap::new();
+  for (area_entity, enemy) in collision_events
+    .read()
+    .filter_map(|event| event.between(CollisionLayer::DamageArea, CollisionLayer::Enemy))
+  {
+    let (Ok((_, area)), Ok(enemy_transform)) = (damage_areas.get(area_entity), enemies.get(enemy))
+    else {
+      continue;
+    };
+    damage_events.send(DamageEvent {
+      target: enemy,
+      amount: area.damage,
+      source: area.source,
+      attack: Some(area_entity),
+      position: enemy_transform.translation.xy(),
+    });
+    *hits_by_area.entry(area_entity).or_default() += 1;
+  }
+
+  // Damage areas only last a single frame, then linger as a visual.
+  for (entity, area) in damage_areas.iter() {
+    let hits = hits_by_area.get(&entity).copied().unwrap_or(0);
+    resolved_events.send(DamageAreaResolvedEvent { hits });
+    commands
+      .entity(entity)
+      .remove::<(DamageArea, Collider, CollisionLayer)>();
+    commands
+      .entity(entity)
+      .insert((
+        DamageFlicker {
+          flicker_timer: Timer::from_seconds(0.05, TimerMode::Repeating),
+          total_duration: Timer::from_seconds(0.2, TimerMode::Once),
+        },
+        Visibility::Inherited,
+      ))
+      // On a child, so scaling the mesh to size leaves the area's own transform alone.
+      .with_child((
+        Transform::from_scale((area.half_size * 2.).extend(1.)),
+        Mesh2d(visuals.area.clone()),
+        MeshMaterial2d(visuals.area_material.clone()),
+      ));
+  }
+}
+
+fn contact_damage(
+  mut commands: Commands,
+  players: Query<(&Transform, Has<Invulnerable>), With<Player>>,
+  enemies: Query<&Transform, With<Enemy>>,
+  mut collision_events: EventReader<CollisionEvent>,
+  mut damage_events: EventWriter<DamageEvent>,
+) {
+  // Only one hit per frame; the invulnerability it grants covers any other enemies touching.
+  let Some((player, enemy)) = collision_events
+    .read()
+    .find_map(|event| event.between(CollisionLayer::Player, CollisionLayer::Enemy))
+  else {
+    return;
+  };
+  collision_events.clear();
+
+  let (Ok((player_transform, invulnerable)), Ok(enemy_transform)) =
+    (players.get(player), enemies.get(enemy))
+  else {
+    return;
+  };
+  // Enemies pass harmlessly through an invulnerable player, so they aren't worn down either.
+  if invulnerable {
+    return;
+  }
+
+  let player_position = player_transform.translation.xy();
+  let enemy_position = enemy_transform.translation.xy();
+
+  damage_events.send(DamageEvent {
+    target: player,
+    amount: CONTACT_DAMAGE,
+    source: DamageSource::Contact,
+    attack: None,
+    position: player_position,
+  });
+  damage_events.send(DamageEvent {
+    target: enemy,
+    amount: CONTACT_DAMAGE,
+    source: DamageSource::Contact,
+    attack: None,
+    position: enemy_position,
+  });
+
+  // Shove the two apart so they don't sit on top of each other once invulnerability wears off.
+  let away_from_enemy = (player_position - enemy_position).normalize_or(Vec2::Y);
+  commands
+    .entity(player)
+    .insert(Knockback(away_from_enemy * PLAYER_KNOCKBACK_SPEED));
+  commands
+    .entity(enemy)
+    .insert(Knockback(-away_from_enemy * ENEMY_KNOCKBACK_SPEED));
+}
+
+fn resolve_damage(
+  mut commands: Commands,
+  mut damage_events: EventReader<DamageEvent>,
+  mut resolved_events: EventWriter<ResolvedDamageEvent>,
+  mut players: Query<&mut Player>,
+  mut enemies: Query<&mut Enemy>,
+  modifiers: Query<(
+    Option<&Armor>,
+    Has<Invulnerable>,
+    Option<&HitInvulnerability>,
+  )>,
+  crits: Res<CriticalHits>,
+) {
+  let mut rng = rand::thread_rng();
+  for event in damage_events.read() {
+    // The target may have been despawned since the hit was detected.
+    let Ok((armor, invulnerable, hit_invulnerability)) = modifiers.get(event.target) else {
+      continue;
+    };
+    if invulnerable {
+      continue;
+    }
+
+    let crit = event.source.can_crit() && rng.gen::<f32>() < crits.chance;
+    let amount = if crit {
+      event.amount * crits.multiplier
+    } else {
+      event.amount
+    };
+    let amount = amount.saturating_sub(armor.map_or(0, |armor| armor.0));
+
+    let hp_lost = if let Ok(mut player) = players.get_mut(event.target) {
+      let hp_before = player.hp;
+      player.hp = player.hp.saturating_sub(amount);
+      hp_before - player.hp
+    } else if let Ok(mut enemy) = enemies.get_mut(event.target) {
+      let hp_before = enemy.hp;
+      enemy.hp = enemy.hp.saturating_sub(amount);
+      if enemy.hp < hp_before {
+        enemy.last_hit_by = Some(event.source);
+        enemy.last_attack = event.attack;
+      }
+      hp_before - enemy.hp
+    } else {
+      0
+    };
+
+    if hp_lost > 0 {
+      if let Some(HitInvulnerability(duration)) = hit_invulnerability {
+        commands
+          .entity(event.target)
+          .insert(Invulnerable(Timer::new(*duration, TimerMode::Once)));
       }
+      resolved_events.send(ResolvedDamageEvent {
+        target: event.target,
+        amount: hp_lost,
+        position: event.position,
+        crit,
+      });
     }
-    commands.entity(entity).remove::<DamageArea>();
-    let damage_area_shape = ShapeBuilder::new()
-      .add(&shapes::Rectangle {
-        extents: area.half_size * 2.,
-        origin: shapes::RectangleOrigin::Center,
-        radii: None,
-      })
-      .fill(Color::srgba(1.0, 1.0, 1.0, 0.2))
-      .build();
-    commands.entity(entity).insert((
+  }
+}
+
+fn spawn_crit_flashes(
+  mut commands: Commands,
+  mut resolved_events: EventReader<ResolvedDamageEvent>,
+  visuals: Res<DamageVisuals>,
+) {
+  for event in resolved_events.read().filter(|event| event.crit) {
+    commands.spawn((
+      Transform::from_translation(event.position.extend(1.0))
+        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+      Mesh2d(visuals.crit.clone()),
+      MeshMaterial2d(visuals.crit_material.clone()),
       DamageFlicker {
         flicker_timer: Timer::from_seconds(0.05, TimerMode::Repeating),
         total_duration: Timer::from_seconds(0.2, TimerMode::Once),
       },
-      damage_area_shape,
       Visibility::Inherited,
     ));
   }
 }
 
-fn contact_damage(
-  mut player: Query<(&Transform, &mut Player)>,
-  mut enemies: Query<(&Transform, &mut Enemy)>,
+// A shield shows itself, so it doesn't flash like being hit does.
+fn flash_invulnerable_player(
+  mut player: Query<(&mut Visibility, Option<&Invulnerable>, Has<Shield>), With<Player>>,
 ) {
-  // TODO the damage rate is a fun effect, but it's frame rate dependent
-  for (enemy_transform, mut enemy) in enemies.iter_mut() {
-    let (player_transform, mut player) = player.single_mut();
-    if (enemy_transform.translation.xy() - player_transform.translation.xy()).length() < 0.5 {
-      player.hp = player.hp.saturating_sub(1);
-      enemy.hp = enemy.hp.saturating_sub(1);
+  for (mut visibility, invulnerable, shielded) in player.iter_mut() {
+    let hidden = !shielded
+      && invulnerable.is_some_and(|invulnerable| {
+        (invulnerable.0.elapsed_secs() * INVULNERABLE_FLASHES_PER_SEC) as u32 % 2 == 1
+      });
+    visibility.set_if_neq(match hidden {
+      true => Visibility::Hidden,
+      false => Visibility::Inherited,
+    });
+  }
+}
+
+fn apply_knockback(
+  mut commands: Commands,
+  mut query: Query<(
+    Entity,
+    &mut Transform,
+    &mut Knockback,
+    &Collider,
+    Has<Player>,
+  )>,
+  time: Res<Time>,
+  play_area: Res<PlayArea>,
+  blockers: Option<Res<ArenaBlockers>>,
+) {
+  let player_bounds = Rect::from_corners(play_area.size_world / 2., play_area.size_world / -2.)
+    .inflate(-MOUSE_RADIUS);
+
+  for (entity, mut transform, mut knockback, collider, is_player) in query.iter_mut() {
+    let mut position = transform.translation.xy() + knockback.0 * time.delta_secs();
+    if is_player {
+      position = position.clamp(player_bounds.min, player_bounds.max);
+      if let Some(blockers) = &blockers {
+        position = blockers.sweep(collider, transform.translation.xy(), position);
+      }
+    }
+    transform.translation = position.extend(transform.translation.z);
+
+    knockback.0 *= (-KNOCKBACK_DAMPING * time.delta_secs()).exp();
+    if knockback.0.length() < 0.1 {
+      commands.entity(entity).remove::<Knockback>();
+    }
+  }
+}
+
+fn tick_invulnerability(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut query: Query<(Entity, &mut Invulnerable)>,
+) {
+  for (entity, mut invulnerable) in query.iter_mut() {
+    if invulnerable.0.tick(time.delta()).finished() {
+      commands.entity(entity).remove::<Invulnerable>();
     }
   }
 }
@@ -105,3 +459,136 @@ fn damage_flicker(
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn app(crits: CriticalHits) -> App {
+    let mut app = App::new();
+    app
+      .add_event::<DamageEvent>()
+      .add_event::<ResolvedDamageEvent>()
+      .insert_resource(crits)
+      .add_systems(Update, resolve_damage);
+    app
+  }
+
+  fn spawn_player(app: &mut App, modifiers: impl Bundle) -> Entity {
+    app
+      .world_mut()
+      .spawn((Player { hp: 30, max_hp: 30 }, modifiers))
+      .id()
+  }
+
+  fn hit(app: &mut App, target: Entity, amount: u32, source: DamageSource) {
+    app.world_mut().send_event(DamageEvent {
+      target,
+      amount,
+      source,
+      attack: None,
+      position: Vec2::ZERO,
+    });
+  }
+
+  // Runs resolution, returning the amount and crit of every hit that landed.
+  fn resolve(app: &mut App) -> Vec<(u32, bool)> {
+    app.update();
+    app
+      .world_mut()
+      .resource_mut::<Events<ResolvedDamageEvent>>()
+      .drain()
+      .map(|event| (event.amount, event.crit))
+      .collect()
+  }
+
+  fn hp(app: &App, player: Entity) -> u32 {
+    app.world().get::<Player>(player).unwrap().hp
+  }
+
+  #[test]
+  fn hits_take_hp_as_sent() {
+    let mut app = app(CriticalHits::default());
+    let player = spawn_player(&mut app, ());
+    hit(&mut app, player, 5, DamageSource::Shot);
+    hit(&mut app, player, 3, DamageSource::Contact);
+    assert_eq!(resolve(&mut app), [(5, false), (3, false)]);
+    assert_eq!(hp(&app, player), 22);
+  }
+
+  #[test]
+  fn armor_reduces_each_hit() {
+    let mut app = app(CriticalHits::default());
+    let player = spawn_player(&mut app, Armor(2));
+    hit(&mut app, player, 5, DamageSource::Contact);
+    // Fully absorbed hits did nothing, so they aren't resolved at all.
+    hit(&mut app, player, 2, DamageSource::Contact);
+    assert_eq!(resolve(&mut app), [(3, false)]);
+    assert_eq!(hp(&app, player), 27);
+  }
+
+  #[test]
+  fn only_the_hp_actually_lost_is_resolved() {
+    let mut app = app(CriticalHits::default());
+    let player = spawn_player(&mut app, ());
+    hit(&mut app, player, 50, DamageSource::Contact);
+    assert_eq!(resolve(&mut app), [(30, false)]);
+    assert_eq!(hp(&app, player), 0);
+  }
+
+  #[test]
+  fn invulnerable_targets_ignore_hits() {
+    let mut app = app(CriticalHits::default());
+    let player = spawn_player(
+      &mut app,
+      Invulnerable(Timer::from_seconds(1., TimerMode::Once)),
+    );
+    hit(&mut app, player, 5, DamageSource::Contact);
+    assert_eq!(resolve(&mut app), []);
+    assert_eq!(hp(&app, player), 30);
+  }
+
+  #[test]
+  fn hit_invulnerability_blocks_later_hits() {
+    let mut app = app(CriticalHits::default());
+    let player = spawn_player(&mut app, HitInvulnerability(Duration::from_secs(1)));
+    hit(&mut app, player, 5, DamageSource::Contact);
+    assert_eq!(resolve(&mut app), [(5, false)]);
+    assert!(app.world().get::<Invulnerable>(player).is_some());
+
+    hit(&mut app, player, 5, DamageSource::Contact);
+    assert_eq!(resolve(&mut app), []);
+    assert_eq!(hp(&app, player), 25);
+  }
+
+  #[test]
+  fn crits_are_off_by_default() {
+    let mut app = app(CriticalHits::default());
+    let player = spawn_player(&mut app, ());
+    for _ in 0..20 {
+      hit(&mut app, player, 1, DamageSource::Shot);
+    }
+    assert!(resolve(&mut app).iter().all(|(_, crit)| !crit));
+  }
+
+  #[test]
+  fn crits_multiply_before_armor_and_skip_contact() {
+    let mut app = app(CriticalHits {
+      chance: 1.,
+      multiplier: 3,
+    });
+    let player = spawn_player(&mut app, Armor(1));
+    hit(&mut app, player, 2, DamageSource::Dash);
+    hit(&mut app, player, 2, DamageSource::Contact);
+    assert_eq!(resolve(&mut app), [(5, true), (1, false)]);
+  }
+
+  #[test]
+  fn hits_on_despawned_targets_are_dropped() {
+    let mut app = app(CriticalHits::default());
+    let player = spawn_player(&mut app, ());
+    app.world_mut().despawn(player);
+    hit(&mut app, player, 5, DamageSource::Contact);
+    assert_eq!(resolve(&mut app), []);
+  }
+}
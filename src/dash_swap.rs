@@ -1,79 +1,113 @@
 use bevy::prelude::*;
-use bevy_prototype_lyon::{
-  prelude::{ShapeBuilder, ShapeBuilderBase},
-  shapes,
-};
 
 use crate::{
-  damage::DamageArea,
-  playing::{MovesStuffSet, Player, Reticle},
-  AppState, MouseControlled,
+  arena::ArenaBlockers,
+  collision::{Collider, CollisionLayer, DetectCollisionsSet, SpatialIndex},
+  damage::{DamageArea, DamageSource},
+  pickups::PowerUps,
+  playing::{Enemy, MovesStuffSet, Player, Reticle, SwapEvent, ENEMY_RADIUS},
+  swap::{Aiming, SwapCharges},
+  upgrades::Upgrades,
+  AppState, GameMode,
 };
 
 pub struct DashSwapPlugin;
 
+pub const DASH_DAMAGE: u32 = 10;
+pub const DASH_HALF_HEIGHT: f32 = 0.5;
+// How far the dash reaches past either end of the swap.
+const DASH_OVERSHOOT: f32 = 0.5;
+const PREVIEW_HIGHLIGHT_COLOR: Color = Color::srgb(1.0, 1.0, 0.6);
+
 impl Plugin for DashSwapPlugin {
   fn build(&self, app: &mut App) {
-    app
-      .add_systems(OnEnter(AppState::Playing), init_resources)
-      .add_systems(
-        Update,
-        dash_swap
-          .in_set(MovesStuffSet)
-          .run_if(in_state(AppState::Playing)),
-      );
+    app.insert_resource(GameMode::DashSwap).add_systems(
+      Update,
+      (
+        dash_swap.after(MovesStuffSet).before(DetectCollisionsSet),
+        preview_dash.after(DetectCollisionsSet),
+      )
+        .run_if(in_state(AppState::Playing)),
+    );
   }
 }
 
-#[derive(Resource)]
-struct PlayerDashTimer(Timer);
-
-fn init_resources(mut commands: Commands) {
-  commands.insert_resource(PlayerDashTimer(Timer::from_seconds(
-    1.0,
-    TimerMode::Repeating,
-  )));
-}
-
-// Swap the player and reticle on a timer, and damage enemies in the player's path.
+// Damage enemies in the player's path whenever they swap.
 fn dash_swap(
   mut commands: Commands,
-  mut dash_timer: ResMut<PlayerDashTimer>,
-  time: Res<Time>,
-  mut player: Query<(&mut Transform, &mut MouseControlled), (With<Player>, Without<Reticle>)>,
-  mut reticle: Query<(&mut Transform, &mut MouseControlled), (With<Reticle>, Without<Player>)>,
+  mut swap_events: EventReader<SwapEvent>,
+  power_ups: Res<PowerUps>,
+  upgrades: Res<Upgrades>,
 ) {
-  if !dash_timer.0.tick(time.delta()).just_finished() {
-    return;
+  for SwapEvent { from, to } in swap_events.read() {
+    let (transform, half_size) = dash_corridor(*from, *to, &upgrades);
+    commands.spawn((
+      transform,
+      DamageArea {
+        damage: upgrades.dash_damage() * power_ups.dash_damage_multiplier(),
+        half_size,
+        source: DamageSource::Dash,
+      },
+      Collider::Obb { half_size },
+      CollisionLayer::DamageArea,
+    ));
   }
+}
 
-  let (mut player_transform, mut player_control) = player.single_mut();
-  let (mut reticle_transform, mut reticle_control) = reticle.single_mut();
-
-  std::mem::swap(
-    &mut player_transform.translation,
-    &mut reticle_transform.translation,
-  );
-  std::mem::swap(
-    &mut player_transform.rotation,
-    &mut reticle_transform.rotation,
+// The area a dash from one point to another covers, centered between them.
+fn dash_corridor(from: Vec2, to: Vec2, upgrades: &Upgrades) -> (Transform, Vec2) {
+  let path = to - from;
+  let mid_position = from + path / 2.0;
+  let transform = Transform::from_translation(mid_position.extend(0.0))
+    .with_rotation(Quat::from_rotation_z(path.to_angle()));
+  let half_size = Vec2::new(
+    path.length() / 2.0 + DASH_OVERSHOOT,
+    upgrades.dash_half_height(),
   );
+  (transform, half_size)
+}
 
-  std::mem::swap(&mut player_control.id, &mut reticle_control.id);
-  std::mem::swap(&mut player_control.hand, &mut reticle_control.hand);
+// Outline where swapping now would dash, stopping at any wall or obstacle in the way, and mark the
+// enemies it would hit. The preview fades in as the next swap charges, and is brightest while aiming.
+fn preview_dash(
+  mut gizmos: Gizmos,
+  (swaps, aiming, upgrades): (Res<SwapCharges>, Res<Aiming>, Res<Upgrades>),
+  player: Query<(&Collider, &Transform), With<Player>>,
+  reticle: Query<&Transform, With<Reticle>>,
+  index: Res<SpatialIndex>,
+  blockers: Res<ArenaBlockers>,
+  enemies: Query<(&Collider, &Transform), With<Enemy>>,
+) {
+  let (Ok((player_collider, player)), Ok(reticle)) = (player.get_single(), reticle.get_single())
+  else {
+    return;
+  };
+  let from = player.translation.xy();
+  let to = blockers.sweep(player_collider, from, reticle.translation.xy());
 
-  // Damage enemies in the player's path.
-  let player_to_reticle = (reticle_transform.translation - player_transform.translation).truncate();
-  let mid_position = player_transform.translation.truncate() + player_to_reticle / 2.0;
-  let half_width = player_to_reticle.length() / 2.0 + 0.5;
-  let half_height = 0.5;
+  let intensity = match aiming.0 {
+    true => 1.,
+    false => 0.1 + 0.5 * swaps.readiness(),
+  };
+  let (transform, half_size) = dash_corridor(from, to, &upgrades);
+  let isometry = Isometry2d::new(
+    transform.translation.xy(),
+    Rot2::radians((to - from).to_angle()),
+  );
+  gizmos.rect_2d(isometry, half_size * 2., Color::WHITE.with_alpha(intensity));
+  gizmos.line_2d(from, to, Color::WHITE.with_alpha(intensity / 2.));
 
-  commands.spawn((
-    Transform::from_translation(mid_position.extend(0.0))
-      .with_rotation(Quat::from_rotation_z(player_to_reticle.to_angle())),
-    DamageArea {
-      damage: 10,
-      half_size: Vec2::new(half_width, half_height),
-    },
-  ));
+  let corridor = Collider::Obb { half_size };
+  for enemy in index.query_rect(corridor.aabb(&transform), CollisionLayer::Enemy) {
+    let Ok((collider, enemy_transform)) = enemies.get(enemy) else {
+      continue;
+    };
+    if corridor.overlaps(&transform, collider, enemy_transform) {
+      gizmos.circle_2d(
+        enemy_transform.translation.xy(),
+        ENEMY_RADIUS + 0.1,
+        PREVIEW_HIGHLIGHT_COLOR.with_alpha(intensity),
+      );
+    }
+  }
 }
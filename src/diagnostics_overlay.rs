@@ -0,0 +1,77 @@
+use bevy::{
+  diagnostic::{DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin},
+  input::common_conditions::input_just_pressed,
+  prelude::*,
+};
+
+pub struct DiagnosticsOverlayPlugin;
+
+impl Plugin for DiagnosticsOverlayPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_plugins((FrameTimeDiagnosticsPlugin, EntityCountDiagnosticsPlugin))
+      .add_systems(Startup, spawn_overlay)
+      .add_systems(
+        Update,
+        (
+          // Shown alongside the world inspector.
+          toggle_overlay.run_if(input_just_pressed(KeyCode::Backquote)),
+          update_overlay,
+        )
+          .chain(),
+      );
+  }
+}
+
+#[derive(Component)]
+struct DiagnosticsOverlay;
+
+fn spawn_overlay(mut commands: Commands) {
+  commands.spawn((
+    DiagnosticsOverlay,
+    Text::default(),
+    TextFont {
+      font_size: 14.0,
+      ..default()
+    },
+    Node {
+      position_type: PositionType::Absolute,
+      top: Val::Px(15.0),
+      right: Val::Px(15.0),
+      ..default()
+    },
+    Visibility::Hidden,
+  ));
+}
+
+fn toggle_overlay(mut overlay: Query<&mut Visibility, With<DiagnosticsOverlay>>) {
+  for mut visibility in overlay.iter_mut() {
+    visibility.toggle_visible_hidden();
+  }
+}
+
+// Lists every registered diagnostic, so plugins only need to register theirs to show up here.
+fn update_overlay(
+  diagnostics: Res<DiagnosticsStore>,
+  mut overlay: Query<(&mut Text, &Visibility), With<DiagnosticsOverlay>>,
+) {
+  for (mut text, visibility) in overlay.iter_mut() {
+    if *visibility == Visibility::Hidden {
+      continue;
+    }
+
+    let mut diagnostics = diagnostics
+      .iter()
+      .filter(|diagnostic| diagnostic.is_enabled)
+      .collect::<Vec<_>>();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.path().as_str());
+    text.0 = diagnostics
+      .iter()
+      .map(|diagnostic| match diagnostic.value() {
+        Some(value) => format!("{}: {:.2}{}", diagnostic.path(), value, diagnostic.suffix),
+        None => format!("{}: -", diagnostic.path()),
+      })
+      .collect::<Vec<_>>()
+      .join("\n");
+  }
+}
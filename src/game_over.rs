@@ -1,7 +1,12 @@
 use bevy::prelude::*;
 
 use crate::{
+  damage::DamageSource,
+  high_scores::InitialsEntry,
   mischief::{MischiefEvent, MischiefEventData},
+  playing::Score,
+  run_stats::RunStats,
+  settings_menu::SettingsMenu,
   AppState,
 };
 
@@ -11,48 +16,120 @@ impl Plugin for GameOverPlugin {
   fn build(&self, app: &mut App) {
     app
       .add_systems(OnEnter(AppState::GameOver), spawn_game_over_text)
-      .add_systems(Update, start_new_game.run_if(in_state(AppState::GameOver)));
+      .add_systems(
+        Update,
+        start_new_game
+          .run_if(in_state(AppState::GameOver))
+          .run_if(in_state(SettingsMenu::Closed)),
+      );
   }
 }
 
-fn spawn_game_over_text(mut commands: Commands) {
+#[derive(Component)]
+pub struct GameOverScreen;
+
+pub fn spawn_game_over_text(mut commands: Commands, score: Res<Score>, stats: Res<RunStats>) {
   println!("Game Over");
   commands
     .spawn((
-      TextLayout {
-        justify: JustifyText::Center,
-
-        ..default()
-      },
-      Text::default(),
       Node {
-        position_type: PositionType::Absolute,
-        align_self: AlignSelf::Center,
-        justify_self: JustifySelf::Center,
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        row_gap: Val::Px(30.0),
         ..default()
       },
+      GameOverScreen,
       StateScoped(AppState::GameOver),
     ))
-    .with_child((
-      TextSpan::new("Game Over!\n"),
-      TextFont {
-        font_size: 60.0,
-        ..default()
-      },
-    ))
-    .with_child((
-      TextSpan::new("Click to restart"),
-      TextFont {
-        font_size: 20.0,
-        ..default()
-      },
-    ));
+    .with_children(|screen| {
+      screen
+        .spawn((
+          TextLayout {
+            justify: JustifyText::Center,
+
+            ..default()
+          },
+          Text::default(),
+        ))
+        .with_child((
+          TextSpan::new("Game Over!\n"),
+          TextFont {
+            font_size: 60.0,
+            ..default()
+          },
+        ))
+        .with_child((
+          TextSpan::new("Click to restart"),
+          TextFont {
+            font_size: 20.0,
+            ..default()
+          },
+        ));
+
+      screen.spawn((
+        Text::new(format_results(&score, &stats)),
+        TextFont {
+          font_size: 20.0,
+          ..default()
+        },
+      ));
+    });
+}
+
+fn format_results(score: &Score, stats: &RunStats) -> String {
+  let time_survived = stats.time_survived.as_secs();
+  let kills_by_source = DamageSource::ALL
+    .iter()
+    .map(|source| format!("{} {}", source.name(), stats.kills_from(*source)))
+    .collect::<Vec<_>>()
+    .join(", ");
+  let hit_rate = match stats.hit_rate() {
+    Some(rate) => format!(
+      "{:.0}% ({}/{} attacks connected)",
+      rate * 100.,
+      stats.attacks_landed,
+      stats.attacks
+    ),
+    None => "-".to_string(),
+  };
+
+  [
+    format!("Final score:     {}", score.0),
+    format!(
+      "Time survived:   {}:{:02}",
+      time_survived / 60,
+      time_survived % 60
+    ),
+    format!("Enemies killed:  {} ({})", stats.kills(), kills_by_source),
+    format!("Damage taken:    {}", stats.damage_taken),
+    format!(
+      "Damage dealt:    {} ({} critical hits)",
+      stats.damage_dealt, stats.crits
+    ),
+    format!("Swaps:           {}", stats.swaps),
+    format!(
+      "Peak spawn rate: {:.1} enemies/sec",
+      stats.peak_enemies_per_sec
+    ),
+    format!("Hit rate:        {}", hit_rate),
+  ]
+  .join("\n")
 }
 
 fn start_new_game(
   mut next_state: ResMut<NextState<AppState>>,
   mut click_events: EventReader<MischiefEvent>,
+  initials_entry: Option<Res<InitialsEntry>>,
 ) {
+  // Clicks are spent picking initials until a new high score has been saved.
+  if initials_entry.is_some() {
+    click_events.clear();
+    return;
+  }
+
   for MischiefEvent {
     device: _,
     event_data,
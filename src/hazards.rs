@@ -0,0 +1,311 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+  collision::{Collider, CollisionLayer, DetectCollisionsSet},
+  playing::Player,
+  window_setup::PlayArea,
+  AppState, EnableStateScopedResource,
+};
+
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .enable_state_scoped_resource::<HazardSchedule>(AppState::Playing)
+      .add_systems(OnEnter(AppState::Playing), init_resources)
+      .add_systems(
+        Update,
+        (
+          spawn_hazards,
+          tick_hazards,
+          place_hazard_parts.before(DetectCollisionsSet),
+          draw_hazards,
+        )
+          .chain()
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+const HAZARD_INTERVAL_SECS: f32 = 10.0;
+// How long each hazard warns before it can hurt anyone.
+const TELEGRAPH_SECS: f32 = 1.5;
+const TELEGRAPH_FLASHES_PER_SEC: f32 = 8.0;
+const HAZARD_COLOR: Color = Color::hsl(0., 0.9, 0.55);
+
+const LASER_SWEEP_SECS: f32 = 3.0;
+const LASER_HALF_WIDTH: f32 = 0.1;
+const SHOCKWAVE_SECS: f32 = 2.0;
+const SHOCKWAVE_HALF_WIDTH: f32 = 0.15;
+const SHOCKWAVE_MAX_RADIUS: f32 = 8.0;
+// The ring isn't convex, so it collides as a loop of boxes laid along it.
+const SHOCKWAVE_SEGMENTS: u32 = 32;
+const PULSE_RADIUS: f32 = 1.5;
+const PULSE_PERIOD_SECS: f32 = 1.5;
+const PULSE_CYCLES: u32 = 4;
+
+#[derive(Resource)]
+struct HazardSchedule(Timer);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HazardKind {
+  // A beam as long as the play area, moving sideways from one edge to the other.
+  Laser {
+    from: Vec2,
+    to: Vec2,
+    half_length: f32,
+  },
+  // A ring expanding out from a point.
+  Shockwave {
+    center: Vec2,
+  },
+  // A floor zone that alternates between safe and lethal, starting safe.
+  Pulse {
+    center: Vec2,
+  },
+}
+
+#[derive(Component, Debug)]
+struct Hazard {
+  kind: HazardKind,
+  telegraph: Timer,
+  active: Timer,
+}
+
+// One of a hazard's colliders, on its own entity since each entity only gets one. It only has a
+// Collider while the hazard can hurt, and goes away along with the hazard.
+#[derive(Component, Debug)]
+struct HazardPart {
+  hazard: Entity,
+  index: u32,
+}
+
+impl Hazard {
+  fn new(kind: HazardKind) -> Self {
+    let active_secs = match kind {
+      HazardKind::Laser { .. } => LASER_SWEEP_SECS,
+      HazardKind::Shockwave { .. } => SHOCKWAVE_SECS,
+      HazardKind::Pulse { .. } => PULSE_PERIOD_SECS * PULSE_CYCLES as f32,
+    };
+    Self {
+      kind,
+      telegraph: Timer::from_seconds(TELEGRAPH_SECS, TimerMode::Once),
+      active: Timer::from_seconds(active_secs, TimerMode::Once),
+    }
+  }
+
+  fn telegraphing(&self) -> bool {
+    !self.telegraph.finished()
+  }
+
+  fn pulse_lethal(&self) -> bool {
+    self.active.elapsed_secs() % PULSE_PERIOD_SECS > PULSE_PERIOD_SECS / 2.
+  }
+
+  fn part_count(&self) -> u32 {
+    match self.kind {
+      HazardKind::Shockwave { .. } => SHOCKWAVE_SEGMENTS,
+      HazardKind::Laser { .. } | HazardKind::Pulse { .. } => 1,
+    }
+  }
+
+  // Where a part is and what shape it is right now, or None while the hazard is harmless.
+  fn part(&self, index: u32) -> Option<(Transform, Collider)> {
+    if self.telegraphing() {
+      return None;
+    }
+    match self.kind {
+      HazardKind::Laser {
+        from,
+        to,
+        half_length,
+      } => {
+        let center = from.lerp(to, self.active.fraction());
+        let sweep_direction = (to - from).normalize_or_zero();
+        let transform = Transform::from_translation(center.extend(0.))
+          .with_rotation(Quat::from_rotation_z(sweep_direction.to_angle()));
+        let half_size = Vec2::new(LASER_HALF_WIDTH, half_length);
+        Some((transform, Collider::Obb { half_size }))
+      }
+      // Each box is tangent to the ring and just long enough to meet its neighbors.
+      HazardKind::Shockwave { center } => {
+        let ring_radius = SHOCKWAVE_MAX_RADIUS * self.active.fraction();
+        let angle = TAU * index as f32 / SHOCKWAVE_SEGMENTS as f32;
+        let position = center + Vec2::from_angle(angle) * ring_radius;
+        let transform = Transform::from_translation(position.extend(0.))
+          .with_rotation(Quat::from_rotation_z(angle));
+        let half_size = Vec2::new(
+          SHOCKWAVE_HALF_WIDTH,
+          ring_radius * (PI / SHOCKWAVE_SEGMENTS as f32).tan(),
+        );
+        Some((transform, Collider::Obb { half_size }))
+      }
+      HazardKind::Pulse { center } => self.pulse_lethal().then(|| {
+        (
+          Transform::from_translation(center.extend(0.)),
+          Collider::Circle {
+            radius: PULSE_RADIUS,
+          },
+        )
+      }),
+    }
+  }
+}
+
+fn init_resources(mut commands: Commands) {
+  commands.insert_resource(HazardSchedule(Timer::from_seconds(
+    HAZARD_INTERVAL_SECS,
+    TimerMode::Repeating,
+  )));
+}
+
+fn spawn_hazards(
+  mut commands: Commands,
+  mut schedule: ResMut<HazardSchedule>,
+  time: Res<Time>,
+  play_area: Res<PlayArea>,
+  player: Query<&Transform, With<Player>>,
+) {
+  if !schedule.0.tick(time.delta()).just_finished() {
+    return;
+  }
+
+  let mut rng = rand::thread_rng();
+  let half_size = play_area.size_world / 2.;
+  let kind = match rng.gen_range(0..3) {
+    0 => {
+      let direction = if rng.gen_bool(0.5) { 1. } else { -1. };
+      if rng.gen_bool(0.5) {
+        HazardKind::Laser {
+          from: Vec2::new(-half_size.x * direction, 0.),
+          to: Vec2::new(half_size.x * direction, 0.),
+          half_length: half_size.y,
+        }
+      } else {
+        HazardKind::Laser {
+          from: Vec2::new(0., -half_size.y * direction),
+          to: Vec2::new(0., half_size.y * direction),
+          half_length: half_size.x,
+        }
+      }
+    }
+    1 => HazardKind::Shockwave {
+      center: rng.gen::<Vec2>() * play_area.size_world - half_size,
+    },
+    // Pulse zones drop right where the player is, so they have to move.
+    _ => HazardKind::Pulse {
+      center: player
+        .get_single()
+        .map_or(Vec2::ZERO, |transform| transform.translation.xy()),
+    },
+  };
+
+  let hazard = Hazard::new(kind);
+  let part_count = hazard.part_count();
+  let hazard = commands
+    .spawn((hazard, StateScoped(AppState::Playing)))
+    .id();
+  for index in 0..part_count {
+    commands.spawn((
+      HazardPart { hazard, index },
+      Transform::default(),
+      CollisionLayer::Hazard,
+      StateScoped(AppState::Playing),
+    ));
+  }
+}
+
+fn tick_hazards(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut hazards: Query<(Entity, &mut Hazard)>,
+) {
+  for (entity, mut hazard) in hazards.iter_mut() {
+    if hazard.telegraphing() {
+      hazard.telegraph.tick(time.delta());
+    } else if hazard.active.tick(time.delta()).finished() {
+      commands.entity(entity).despawn_recursive();
+    }
+  }
+}
+
+// Hazards then hurt the player the same way the arena's hazard pieces do.
+fn place_hazard_parts(
+  mut commands: Commands,
+  hazards: Query<&Hazard>,
+  mut parts: Query<(Entity, &HazardPart, &mut Transform)>,
+) {
+  for (entity, part, mut transform) in parts.iter_mut() {
+    let Ok(hazard) = hazards.get(part.hazard) else {
+      commands.entity(entity).despawn();
+      continue;
+    };
+    match hazard.part(part.index) {
+      Some((part_transform, collider)) => {
+        *transform = part_transform;
+        commands.entity(entity).insert(collider);
+      }
+      None => {
+        commands.entity(entity).remove::<Collider>();
+      }
+    }
+  }
+}
+
+// Telegraphs flicker where the hazard is about to appear, then the hazard itself is drawn solid.
+fn draw_hazards(mut gizmos: Gizmos, hazards: Query<&Hazard>) {
+  for hazard in hazards.iter() {
+    let color = match hazard.telegraphing() {
+      true => {
+        let flash = (hazard.telegraph.elapsed_secs() * TELEGRAPH_FLASHES_PER_SEC) as u32 % 2;
+        HAZARD_COLOR.with_alpha(0.2 + 0.3 * flash as f32)
+      }
+      false => HAZARD_COLOR,
+    };
+
+    match hazard.kind {
+      HazardKind::Laser {
+        from,
+        to,
+        half_length,
+      } => {
+        let center = from.lerp(to, hazard.active.fraction());
+        let sweep_direction = (to - from).normalize_or_zero();
+        let isometry = Isometry2d::new(center, Rot2::radians(sweep_direction.to_angle()));
+        let size = Vec2::new(LASER_HALF_WIDTH, half_length) * 2.;
+        gizmos.rect_2d(isometry, size, color);
+        if hazard.telegraphing() {
+          // Point the way it's going to sweep.
+          gizmos.arrow_2d(center, center + sweep_direction, color);
+        }
+      }
+      HazardKind::Shockwave { center } => {
+        if hazard.telegraphing() {
+          gizmos.circle_2d(center, SHOCKWAVE_HALF_WIDTH * 2., color);
+          gizmos.circle_2d(center, SHOCKWAVE_MAX_RADIUS, color.with_alpha(0.1));
+        } else {
+          let ring_radius = SHOCKWAVE_MAX_RADIUS * hazard.active.fraction();
+          gizmos.circle_2d(center, ring_radius + SHOCKWAVE_HALF_WIDTH, color);
+          gizmos.circle_2d(center, (ring_radius - SHOCKWAVE_HALF_WIDTH).max(0.), color);
+        }
+      }
+      HazardKind::Pulse { center } => {
+        let lethal = !hazard.telegraphing() && hazard.pulse_lethal();
+        let color = match (hazard.telegraphing(), lethal) {
+          (true, _) => color,
+          (false, true) => HAZARD_COLOR,
+          (false, false) => HAZARD_COLOR.with_alpha(0.3),
+        };
+        gizmos.circle_2d(center, PULSE_RADIUS, color);
+        if lethal {
+          gizmos.circle_2d(center, PULSE_RADIUS * 0.8, color);
+          gizmos.circle_2d(center, PULSE_RADIUS * 0.6, color);
+        }
+      }
+    }
+  }
+}
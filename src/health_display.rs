@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+
+// Health is shown on a child mesh that's filled in proportion to what's left. Every fill level is
+// built up front, so showing a new one just swaps which mesh the display uses.
+
+pub trait Health: Component {
+  fn hp(&self) -> u32;
+  fn max_hp(&self) -> u32;
+}
+
+// One fill per level, from empty up to full. Max hp can differ from the number of levels, in which
+// case the nearest level up is shown.
+#[derive(Component, Clone)]
+pub struct HealthDisplay {
+  pub fills: Vec<Handle<Mesh>>,
+}
+
+// Only looks at things whose health changed this frame, which is almost never most of them.
+pub fn display_health<T: Health>(
+  owners: Query<(&T, &Children), Changed<T>>,
+  mut displays: Query<(&mut Mesh2d, &HealthDisplay)>,
+) {
+  for (owner, children) in owners.iter() {
+    for child in children.iter() {
+      let Ok((mut mesh, display)) = displays.get_mut(*child) else {
+        continue;
+      };
+
+      let levels = display.fills.len() as u32 - 1;
+      let max_hp = owner.max_hp().max(1);
+      let level = (owner.hp().min(max_hp) * levels).div_ceil(max_hp);
+      mesh.0 = display.fills[level as usize].clone();
+    }
+  }
+}
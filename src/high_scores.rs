@@ -0,0 +1,410 @@
+use std::{
+  collections::HashMap,
+  fmt::Write,
+  path::PathBuf,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  game_over::{spawn_game_over_text, GameOverScreen},
+  mischief::{MischiefEvent, MischiefEventData},
+  playing::Score,
+  ron_file,
+  run_stats::RunStats,
+  settings_menu::SettingsMenu,
+  AppState, EnableStateScopedResource, GameMode,
+};
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .insert_resource(HighScores::load())
+      .enable_state_scoped_resource::<RunRank>(AppState::GameOver)
+      .enable_state_scoped_resource::<InitialsEntry>(AppState::GameOver)
+      .add_systems(
+        OnEnter(AppState::GameOver),
+        (rank_run, spawn_high_score_display)
+          .chain()
+          .after(spawn_game_over_text),
+      )
+      .add_systems(
+        Update,
+        (
+          enter_initials.run_if(in_state(SettingsMenu::Closed)),
+          update_high_score_display,
+        )
+          .chain()
+          .run_if(in_state(AppState::GameOver)),
+      );
+  }
+}
+
+const MAX_ENTRIES: usize = 10;
+const INITIALS_LEN: usize = 3;
+// Raw vertical mouse counts needed to step to the next letter.
+const COUNTS_PER_LETTER: i32 = 40;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HighScoreEntry {
+  pub score: i32,
+  pub time_survived_secs: f32,
+  pub kills: u32,
+  // Seconds since the unix epoch.
+  pub date: u64,
+  pub initials: String,
+}
+
+// Top scores for each game mode, persisted to the platform data directory.
+#[derive(Resource, Serialize, Deserialize, Debug, Default)]
+pub struct HighScores {
+  tables: HashMap<GameMode, Vec<HighScoreEntry>>,
+}
+
+impl HighScores {
+  fn path() -> Option<PathBuf> {
+    ron_file::path_in(dirs::data_dir(), "high_scores.ron")
+  }
+
+  fn load() -> Self {
+    ron_file::load(Self::path().as_deref(), "high scores")
+  }
+
+  fn save(&self) {
+    ron_file::save(self, Self::path().as_deref(), "high scores");
+  }
+
+  pub fn table(&self, mode: GameMode) -> &[HighScoreEntry] {
+    self.tables.get(&mode).map_or(&[], Vec::as_slice)
+  }
+
+  // The zero-based rank a score would take in a mode's table, if it makes the cut.
+  // Ties go below existing entries, so the first to reach a score keeps the higher rank.
+  pub fn rank_for(&self, mode: GameMode, score: i32) -> Option<usize> {
+    let table = self.table(mode);
+    let rank = table
+      .iter()
+      .position(|entry| score > entry.score)
+      .unwrap_or(table.len());
+    (rank < MAX_ENTRIES).then_some(rank)
+  }
+
+  fn insert(&mut self, mode: GameMode, entry: HighScoreEntry) -> Option<usize> {
+    let rank = self.rank_for(mode, entry.score)?;
+    let table = self.tables.entry(mode).or_default();
+    table.insert(rank, entry);
+    table.truncate(MAX_ENTRIES);
+    Some(rank)
+  }
+}
+
+// Where the last run placed in the high score table, if it placed at all.
+#[derive(Resource, Debug)]
+struct RunRank(Option<usize>);
+
+// Present while the player is picking initials for a new high score.
+#[derive(Resource, Debug)]
+pub struct InitialsEntry {
+  letters: [u8; INITIALS_LEN],
+  cursor: usize,
+  motion: i32,
+}
+
+impl Default for InitialsEntry {
+  fn default() -> Self {
+    Self {
+      letters: [b'A'; INITIALS_LEN],
+      cursor: 0,
+      motion: 0,
+    }
+  }
+}
+
+impl InitialsEntry {
+  fn step_letter(&mut self, steps: i32) {
+    let letter = &mut self.letters[self.cursor];
+    *letter = b'A' + (*letter as i32 - b'A' as i32 + steps).rem_euclid(26) as u8;
+  }
+
+  fn initials(&self) -> String {
+    String::from_utf8_lossy(&self.letters).into_owned()
+  }
+}
+
+fn rank_run(
+  mut commands: Commands,
+  high_scores: Res<HighScores>,
+  mode: Res<GameMode>,
+  score: Res<Score>,
+) {
+  let rank = high_scores.rank_for(*mode, score.0);
+  if rank.is_some() {
+    commands.insert_resource(InitialsEntry::default());
+  }
+  commands.insert_resource(RunRank(rank));
+}
+
+fn enter_initials(
+  mut commands: Commands,
+  entry: Option<ResMut<InitialsEntry>>,
+  mut mouse_events: EventReader<MischiefEvent>,
+  mut high_scores: ResMut<HighScores>,
+  mut run_rank: ResMut<RunRank>,
+  (mode, score, stats): (Res<GameMode>, Res<Score>, Res<RunStats>),
+) {
+  let Some(mut entry) = entry else {
+    mouse_events.clear();
+    return;
+  };
+
+  for MischiefEvent {
+    device: _,
+    event_data,
+  } in mouse_events.read()
+  {
+    match event_data {
+      MischiefEventData::RelMotion { x: _, y } => {
+        // Moving either mouse down scrolls forward through the alphabet.
+        entry.motion += y;
+        let steps = entry.motion / COUNTS_PER_LETTER;
+        entry.motion -= steps * COUNTS_PER_LETTER;
+        entry.step_letter(steps);
+      }
+      MischiefEventData::Button {
+        button: _,
+        pressed: true,
+      } => {
+        entry.cursor += 1;
+        entry.motion = 0;
+        if entry.cursor < INITIALS_LEN {
+          continue;
+        }
+
+        let date = SystemTime::now()
+          .duration_since(UNIX_EPOCH)
+          .map_or(0, |since_epoch| since_epoch.as_secs());
+        run_rank.0 = high_scores.insert(
+          *mode,
+          HighScoreEntry {
+            score: score.0,
+            time_survived_secs: stats.time_survived.as_secs_f32(),
+            kills: stats.kills(),
+            date,
+            initials: entry.initials(),
+          },
+        );
+        high_scores.save();
+        commands.remove_resource::<InitialsEntry>();
+        return;
+      }
+      _ => {}
+    }
+  }
+}
+
+#[derive(Component)]
+struct HighScoreDisplay;
+
+fn spawn_high_score_display(mut commands: Commands, screen: Query<Entity, With<GameOverScreen>>) {
+  commands.entity(screen.single()).with_child((
+    Text::default(),
+    TextFont {
+      font_size: 18.0,
+      ..default()
+    },
+    HighScoreDisplay,
+  ));
+}
+
+fn update_high_score_display(
+  high_scores: Res<HighScores>,
+  entry: Option<Res<InitialsEntry>>,
+  run_rank: Res<RunRank>,
+  mode: Res<GameMode>,
+  mut text: Query<&mut Text, With<HighScoreDisplay>>,
+) {
+  let mut contents = String::new();
+
+  match (&entry, run_rank.0) {
+    (Some(entry), Some(rank)) => {
+      let letters = entry
+        .letters
+        .iter()
+        .enumerate()
+        .map(|(i, letter)| match i.cmp(&entry.cursor) {
+          std::cmp::Ordering::Less => format!(" {} ", *letter as char),
+          std::cmp::Ordering::Equal => format!("[{}]", *letter as char),
+          std::cmp::Ordering::Greater => " _ ".to_string(),
+        })
+        .collect::<String>();
+      let _ = writeln!(contents, "New high score, rank #{}!", rank + 1);
+      let _ = writeln!(contents, "Enter your initials: {}", letters);
+      let _ = writeln!(
+        contents,
+        "(move a mouse up/down to pick, click to confirm)\n"
+      );
+    }
+    (None, Some(rank)) => {
+      let _ = writeln!(contents, "You placed #{}\n", rank + 1);
+    }
+    (_, None) => {
+      let _ = writeln!(contents, "No high score this time\n");
+    }
+  }
+
+  let _ = writeln!(contents, "Top {} - {}", MAX_ENTRIES, mode.name());
+  for (i, entry) in high_scores.table(*mode).iter().enumerate() {
+    let time_survived = entry.time_survived_secs as u32;
+    let _ = writeln!(
+      contents,
+      "{:>2}. {:<3} {:>6} {:>3}:{:02} {:>4} kills  {}",
+      i + 1,
+      entry.initials,
+      entry.score,
+      time_survived / 60,
+      time_survived % 60,
+      entry.kills,
+      format_date(entry.date),
+    );
+  }
+
+  for mut text in text.iter_mut() {
+    text.0.clone_from(&contents);
+  }
+}
+
+// Formats seconds since the unix epoch as a UTC YYYY-MM-DD date.
+fn format_date(unix_secs: u64) -> String {
+  // Days-to-civil conversion from Howard Hinnant's date algorithms.
+  let days = (unix_secs / 86_400) as i64 + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let shifted_month = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+  let month = if shifted_month < 10 {
+    shifted_month + 3
+  } else {
+    shifted_month - 9
+  };
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn entry(score: i32, initials: &str) -> HighScoreEntry {
+    HighScoreEntry {
+      score,
+      time_survived_secs: 0.,
+      kills: 0,
+      date: 0,
+      initials: initials.to_string(),
+    }
+  }
+
+  fn scores(high_scores: &HighScores, mode: GameMode) -> Vec<i32> {
+    high_scores
+      .table(mode)
+      .iter()
+      .map(|entry| entry.score)
+      .collect()
+  }
+
+  // Scores 100, 90, ..., 10.
+  fn full_table(mode: GameMode) -> HighScores {
+    let mut high_scores = HighScores::default();
+    for i in 0..MAX_ENTRIES as i32 {
+      high_scores.insert(mode, entry(100 - 10 * i, "AAA"));
+    }
+    high_scores
+  }
+
+  #[test]
+  fn anything_places_in_an_empty_table() {
+    let high_scores = HighScores::default();
+    assert_eq!(high_scores.rank_for(GameMode::Shoot, -5), Some(0));
+  }
+
+  #[test]
+  fn ties_rank_below_existing_entries() {
+    let mut high_scores = HighScores::default();
+    high_scores.insert(GameMode::DashSwap, entry(100, "AAA"));
+    assert_eq!(high_scores.rank_for(GameMode::DashSwap, 100), Some(1));
+    assert_eq!(
+      high_scores.insert(GameMode::DashSwap, entry(100, "BBB")),
+      Some(1)
+    );
+    assert_eq!(high_scores.rank_for(GameMode::DashSwap, 101), Some(0));
+
+    let initials = high_scores
+      .table(GameMode::DashSwap)
+      .iter()
+      .map(|entry| entry.initials.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(initials, ["AAA", "BBB"]);
+  }
+
+  #[test]
+  fn full_tables_drop_their_lowest_entry() {
+    let mut high_scores = full_table(GameMode::DashSwap);
+    assert_eq!(
+      high_scores.insert(GameMode::DashSwap, entry(55, "NEW")),
+      Some(5)
+    );
+    assert_eq!(
+      scores(&high_scores, GameMode::DashSwap),
+      [100, 90, 80, 70, 60, 55, 50, 40, 30, 20]
+    );
+  }
+
+  #[test]
+  fn scores_below_a_full_table_do_not_place() {
+    let mut high_scores = full_table(GameMode::DashSwap);
+    // Tying the last entry would put it just past the end.
+    assert_eq!(high_scores.rank_for(GameMode::DashSwap, 10), None);
+    assert_eq!(high_scores.rank_for(GameMode::DashSwap, 5), None);
+    assert_eq!(
+      high_scores.insert(GameMode::DashSwap, entry(5, "NEW")),
+      None
+    );
+    assert_eq!(high_scores.table(GameMode::DashSwap).len(), MAX_ENTRIES);
+    assert_eq!(scores(&high_scores, GameMode::DashSwap).last(), Some(&10));
+  }
+
+  #[test]
+  fn modes_have_separate_tables() {
+    let mut high_scores = full_table(GameMode::DashSwap);
+    assert_eq!(high_scores.rank_for(GameMode::BombSurprise, 5), Some(0));
+    high_scores.insert(GameMode::BombSurprise, entry(5, "BOM"));
+    assert_eq!(scores(&high_scores, GameMode::BombSurprise), [5]);
+    assert_eq!(high_scores.table(GameMode::DashSwap).len(), MAX_ENTRIES);
+  }
+
+  #[test]
+  fn dates_format_as_utc_days() {
+    assert_eq!(format_date(0), "1970-01-01");
+    assert_eq!(format_date(1_792_281_600), "2026-10-18");
+    // The last second of a day is still that day.
+    assert_eq!(format_date(1_792_281_600 - 1), "2026-10-17");
+  }
+
+  #[test]
+  fn leap_days_format_correctly() {
+    assert_eq!(format_date(1_709_164_800), "2024-02-29");
+    // 2000 is a leap year even though it's a century.
+    assert_eq!(format_date(951_782_400), "2000-02-29");
+    assert_eq!(format_date(951_868_800), "2000-03-01");
+    // 2100 isn't, so February runs straight into March.
+    assert_eq!(format_date(4_107_542_400 - 86_400), "2100-02-28");
+    assert_eq!(format_date(4_107_542_400), "2100-03-01");
+  }
+}
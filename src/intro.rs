@@ -1,12 +1,9 @@
 use bevy::prelude::*;
 
 use crate::{
-  apply_mouse_events,
-  mischief::MischiefSession,
-  path::{Path, WindDirection},
-  window_setup::PlayArea,
-  AppState, Hand, MouseControlConfig, MouseControlled, MOUSE_RADIUS, PLAYER_COLOR, RETICLE_COLOR,
-  UNASSIGNED_COLOR,
+  apply_mouse_events, mischief::MischiefSession, path::Path, settings_menu::SettingsMenu,
+  window_setup::PlayArea, AppState, Hand, MouseControlConfig, MouseControlled, MOUSE_RADIUS,
+  PLAYER_COLOR, RETICLE_COLOR, UNASSIGNED_COLOR,
 };
 
 pub struct IntroPlugin;
@@ -20,7 +17,8 @@ impl Plugin for IntroPlugin {
         (assign_cursor_hands, (color_cursors, progress_intro))
           .chain()
           .after(apply_mouse_events)
-          .run_if(in_state(AppState::Intro)),
+          .run_if(in_state(AppState::Intro))
+          .run_if(in_state(SettingsMenu::Closed)),
       )
       .add_systems(OnExit(AppState::Intro), cleanup_intro);
   }
@@ -29,88 +27,19 @@ impl Plugin for IntroPlugin {
 fn make_box_mesh(outer_size: Vec2, border_thickness: f32, outer_corner_radius: f32) -> Mesh {
   assert!(outer_corner_radius > border_thickness);
 
-  let left_edge = -outer_size.x / 2.0;
-  let right_edge = outer_size.x / 2.0;
-  let top_edge = outer_size.y / 2.0;
-  let bottom_edge = -outer_size.y / 2.0;
-
-  let left_curve = left_edge + outer_corner_radius;
-  let right_curve = right_edge - outer_corner_radius;
-  let top_curve = top_edge - outer_corner_radius;
-  let bottom_curve = bottom_edge + outer_corner_radius;
-
-  let inside_left_edge = left_edge + border_thickness;
-  let inside_right_edge = right_edge - border_thickness;
-  let inside_top_edge = top_edge - border_thickness;
-  let inside_bottom_edge = bottom_edge + border_thickness;
-
-  let mut path: Path = Path::new();
-  // Draw the outer rectangle (with rounded corners)
-  // Going counterclockwise from the top left before the bevel
-  path.move_to(Vec2::new(left_curve, top_edge));
-  path.arc_to(
-    Vec2::new(left_edge, top_curve),
-    Vec2::new(left_curve, top_curve),
-    8,
-    WindDirection::CounterClockwise,
-  );
-  path.line_to(Vec2::new(left_edge, bottom_curve));
-  path.arc_to(
-    Vec2::new(left_curve, bottom_edge),
-    Vec2::new(left_curve, bottom_curve),
-    8,
-    WindDirection::CounterClockwise,
-  );
-  path.line_to(Vec2::new(right_curve, bottom_edge));
-  path.arc_to(
-    Vec2::new(right_edge, bottom_curve),
-    Vec2::new(right_curve, bottom_curve),
+  // The inner corners curve around the same centers as the outer ones.
+  let mut path = Path::rounded_rect(outer_size / 2.0, outer_corner_radius, 8);
+  let mut hole = Path::rounded_rect(
+    outer_size / 2.0 - border_thickness,
+    outer_corner_radius - border_thickness,
     8,
-    WindDirection::CounterClockwise,
   );
-  path.line_to(Vec2::new(right_edge, top_curve));
-  path.arc_to(
-    Vec2::new(right_curve, top_edge),
-    Vec2::new(right_curve, top_curve),
-    8,
-    WindDirection::CounterClockwise,
-  );
-  path.line_to(Vec2::new(left_curve + 0.0001, top_edge));
+  hole.reverse_winding_order();
+  path.append(&hole);
 
-  // Now the inner rectangle (again with rounded corners)
-  // And we go clockwise from the top left
-  path.line_to(Vec2::new(left_curve + 0.0001, inside_top_edge));
-  path.line_to(Vec2::new(right_curve, inside_top_edge));
-  path.arc_to(
-    Vec2::new(inside_right_edge, top_curve),
-    Vec2::new(right_curve, top_curve),
-    8,
-    WindDirection::Clockwise,
-  );
-  path.line_to(Vec2::new(inside_right_edge, bottom_curve));
-  path.arc_to(
-    Vec2::new(right_curve, inside_bottom_edge),
-    Vec2::new(right_curve, bottom_curve),
-    8,
-    WindDirection::Clockwise,
-  );
-  path.line_to(Vec2::new(left_curve, inside_bottom_edge));
-  path.arc_to(
-    Vec2::new(inside_left_edge, bottom_curve),
-    Vec2::new(left_curve, bottom_curve),
-    8,
-    WindDirection::Clockwise,
-  );
-  path.line_to(Vec2::new(inside_left_edge, top_curve));
-  path.arc_to(
-    Vec2::new(left_curve, inside_top_edge),
-    Vec2::new(left_curve, top_curve),
-    8,
-    WindDirection::Clockwise,
-  );
-  path.close();
-
-  path.build_triangle_mesh()
+  path
+    .build_triangle_mesh()
+    .expect("box outlines are simple polygons")
 }
 
 fn spawn_boxes(
@@ -266,3 +195,49 @@ fn cleanup_intro(mut commands: Commands, cursors: Query<(Entity, &MouseControlle
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use bevy::render::mesh::VertexAttributeValues;
+
+  use super::*;
+
+  // Compares against meshes saved under testdata. After changing how the boxes are meant to look,
+  // rerun with UPDATE_GOLDEN=1 to save the new ones.
+  fn assert_matches_golden(name: &str, mesh: &Mesh) {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+      mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+      panic!("{} has no positions", name);
+    };
+    let path = format!("{}/testdata/{}.ron", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+      let config = ron::ser::PrettyConfig::default().compact_arrays(true);
+      let contents = ron::ser::to_string_pretty(positions, config).unwrap();
+      std::fs::write(&path, contents + "\n").unwrap();
+    }
+
+    let golden: Vec<[f32; 3]> = ron::from_str(
+      &std::fs::read_to_string(&path).unwrap_or_else(|err| panic!("reading {}: {}", path, err)),
+    )
+    .unwrap();
+    assert_eq!(positions.len(), golden.len(), "{} vertex count", name);
+    for (i, (actual, expected)) in positions.iter().zip(&golden).enumerate() {
+      assert!(
+        Vec3::from(*actual).abs_diff_eq(Vec3::from(*expected), 1e-5),
+        "{} vertex {} is {:?}, expected {:?}",
+        name,
+        i,
+        actual,
+        expected
+      );
+    }
+  }
+
+  #[test]
+  fn box_meshes_match_golden() {
+    // The size the intro uses for the default window, and a squarer one.
+    assert_matches_golden("intro_box", &make_box_mesh(Vec2::new(3.5, 8.0), 0.05, 0.5));
+    assert_matches_golden("square_box", &make_box_mesh(Vec2::new(2.0, 2.0), 0.2, 0.5));
+  }
+}
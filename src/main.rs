@@ -1,26 +1,62 @@
 use std::collections::HashMap;
 
+use arena::{ArenaBlockers, ArenaPlugin};
+use audio::SoundPlugin;
 use bevy::{input::common_conditions::input_toggle_active, prelude::*};
-use bevy_prototype_lyon::plugin::ShapePlugin;
 use bomb_surprise::BombSurprisePlugin;
+use camera_fx::CameraFxPlugin;
+use collision::{Collider, CollisionPlugin};
+use combo::ComboPlugin;
 use damage::DamagePlugin;
 use dash_swap::DashSwapPlugin;
+use diagnostics_overlay::DiagnosticsOverlayPlugin;
 use game_over::GameOverPlugin;
+use hazards::HazardsPlugin;
+use high_scores::HighScoresPlugin;
 use intro::IntroPlugin;
 use mischief::{MischiefEvent, MischiefPlugin};
-use playing::{MovesStuffSet, PlayingPlugin};
+use particles::ParticlesPlugin;
+use pickups::PickupsPlugin;
+use playing::{MovesStuffSet, Player, PlayingPlugin};
+use run_stats::RunStatsPlugin;
+use serde::{Deserialize, Serialize};
+use settings::SettingsPlugin;
+use settings_menu::SettingsMenuPlugin;
 use shoot::ShootPlugin;
+use swap::SwapPlugin;
+use upgrades::UpgradesPlugin;
+use weapons::WeaponsPlugin;
 use window_setup::{PlayArea, WindowSetupPlugin};
 
+mod arena;
+mod audio;
 mod bomb_surprise;
+mod camera_fx;
+mod collision;
+mod combo;
 mod damage;
 mod dash_swap;
+mod diagnostics_overlay;
 mod game_over;
+mod hazards;
+mod health_display;
+mod high_scores;
 mod intro;
 mod mischief;
+mod particles;
 mod path;
+mod pickups;
 mod playing;
+mod ron_file;
+mod run_stats;
+mod settings;
+mod settings_menu;
+mod sfx;
 mod shoot;
+mod spatial_grid;
+mod swap;
+mod upgrades;
+mod weapons;
 mod window_setup;
 
 const MOUSE_RADIUS: f32 = 0.4;
@@ -42,16 +78,31 @@ const RETICLE_COLOR: Color = Color::hsl(300., 0.95, 0.7);
 fn main() {
   App::new()
     .add_plugins(DefaultPlugins)
-    .add_plugins(ShapePlugin)
     .add_plugins(WindowSetupPlugin)
+    .add_plugins(SettingsPlugin)
+    .add_plugins(SettingsMenuPlugin)
+    .add_plugins(CameraFxPlugin)
+    .add_plugins(DiagnosticsOverlayPlugin)
     .add_plugins(MischiefPlugin)
     .add_plugins(IntroPlugin)
     .add_plugins(PlayingPlugin)
+    .add_plugins(CollisionPlugin)
+    .add_plugins(ArenaPlugin)
+    .add_plugins(HazardsPlugin)
+    .add_plugins(SwapPlugin)
     .add_plugins(DamagePlugin)
+    .add_plugins(WeaponsPlugin)
     // .add_plugins(ShootPlugin)
     .add_plugins(DashSwapPlugin)
     // .add_plugins(BombSurprisePlugin)
     .add_plugins(GameOverPlugin)
+    .add_plugins(RunStatsPlugin)
+    .add_plugins(ComboPlugin)
+    .add_plugins(HighScoresPlugin)
+    .add_plugins(PickupsPlugin)
+    .add_plugins(ParticlesPlugin)
+    .add_plugins(SoundPlugin)
+    .add_plugins(UpgradesPlugin)
     .insert_state(AppState::Loading)
     .enable_state_scoped_entities::<AppState>()
     .add_event::<CursorMoveEvent>()
@@ -88,6 +139,33 @@ enum AppState {
   GameOver,
 }
 
+// Play stops while the player drafts an upgrade.
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[source(AppState = AppState::Playing)]
+enum PlayPhase {
+  #[default]
+  Running,
+  Drafting,
+}
+
+// Which swap mechanic is in play. Each mode plugin inserts its own variant.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum GameMode {
+  DashSwap,
+  BombSurprise,
+  Shoot,
+}
+
+impl GameMode {
+  fn name(&self) -> &'static str {
+    match self {
+      GameMode::DashSwap => "Dash Swap",
+      GameMode::BombSurprise => "Bomb Surprise",
+      GameMode::Shoot => "Shoot",
+    }
+  }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum Hand {
   Left,
@@ -151,18 +229,24 @@ fn aggregate_mouse_events(
 
 fn apply_mouse_events(
   mut mouse_events: EventReader<CursorMoveEvent>,
-  mut mouse_controlled: Query<(&mut Transform, &MouseControlled)>,
+  mut mouse_controlled: Query<(
+    &mut Transform,
+    &MouseControlled,
+    Option<&Collider>,
+    Has<Player>,
+  )>,
   time: Res<Time>,
   play_area: Res<PlayArea>,
+  blockers: Option<Res<ArenaBlockers>>,
 ) {
   for CursorMoveEvent {
     device,
     delta_world,
   } in mouse_events.read()
   {
-    for (mut transform, mc) in mouse_controlled
+    for (mut transform, mc, collider, is_player) in mouse_controlled
       .iter_mut()
-      .filter(|(_, mc)| mc.id == *device)
+      .filter(|(_, mc, _, _)| mc.id == *device)
     {
       let valid_positions =
         Rect::from_corners(play_area.size_world / 2., play_area.size_world / -2.)
@@ -175,8 +259,12 @@ fn apply_mouse_events(
         }
       };
 
-      let next_pos = (transform.translation.xy() + velocity_clamped_delta_world)
+      let mut next_pos = (transform.translation.xy() + velocity_clamped_delta_world)
         .clamp(valid_positions.min, valid_positions.max);
+      // Walls stop the player, but not the reticle.
+      if let (true, Some(collider), Some(blockers)) = (is_player, collider, &blockers) {
+        next_pos = blockers.sweep(collider, transform.translation.xy(), next_pos);
+      }
 
       transform.translation = next_pos.extend(transform.translation.z);
     }
@@ -0,0 +1,260 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+  diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+  prelude::*,
+};
+use rand::Rng;
+
+use crate::{
+  damage::ResolvedDamageEvent,
+  pickups::Pickup,
+  playing::{EnemyKilledEvent, Player, SwapEvent},
+  AppState, PLAYER_COLOR,
+};
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_event::<ParticleBurstEvent>()
+      .insert_resource(SparkleTimer(Timer::from_seconds(
+        SPARKLE_INTERVAL_SECS,
+        TimerMode::Repeating,
+      )))
+      .register_diagnostic(Diagnostic::new(PARTICLES_LIVE))
+      .add_systems(Startup, spawn_particle_pool)
+      .add_systems(
+        Update,
+        (
+          (
+            enemy_death_bursts,
+            dash_trails,
+            player_hit_sparks,
+            pickup_sparkles.run_if(in_state(AppState::Playing)),
+          ),
+          emit_particles,
+          update_particles,
+          measure_particles,
+        )
+          .chain(),
+      );
+  }
+}
+
+// Every particle is spawned up front. Bursts that would go over this are cut short, so a screen full
+// of explosions costs the same as a quiet one.
+const MAX_PARTICLES: usize = 1024;
+// How quickly particles slow down, per second.
+const PARTICLE_DRAG: f32 = 3.0;
+const SPARKLE_INTERVAL_SECS: f32 = 0.2;
+// Particles per world unit of a dash trail.
+const TRAIL_DENSITY: f32 = 6.0;
+
+const PARTICLES_LIVE: DiagnosticPath = DiagnosticPath::const_new("particles/live");
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emitter {
+  Point(Vec2),
+  // Spread evenly along a line segment.
+  Line(Vec2, Vec2),
+}
+
+// Asks for particles to fly out from an emitter in random directions, at up to the given speed.
+#[derive(Event, Debug, Clone)]
+pub struct ParticleBurstEvent {
+  pub emitter: Emitter,
+  pub count: usize,
+  pub color: Color,
+  pub speed: f32,
+  pub size: f32,
+  pub lifetime_secs: f32,
+}
+
+#[derive(Component, Debug)]
+struct Particle {
+  velocity: Vec2,
+  color: Color,
+  size: f32,
+  lifetime: Timer,
+}
+
+// Particles that aren't in use, ready to be handed out to the next burst.
+#[derive(Resource, Debug)]
+struct ParticlePool {
+  free: Vec<Entity>,
+}
+
+#[derive(Resource)]
+struct SparkleTimer(Timer);
+
+fn spawn_particle_pool(mut commands: Commands) {
+  let free = (0..MAX_PARTICLES)
+    .map(|_| {
+      commands
+        .spawn((
+          Particle {
+            velocity: Vec2::ZERO,
+            color: Color::WHITE,
+            size: 0.,
+            lifetime: Timer::default(),
+          },
+          Sprite::default(),
+          Transform::default(),
+          Visibility::Hidden,
+        ))
+        .id()
+    })
+    .collect();
+  commands.insert_resource(ParticlePool { free });
+}
+
+fn enemy_death_bursts(
+  mut killed_events: EventReader<EnemyKilledEvent>,
+  mut bursts: EventWriter<ParticleBurstEvent>,
+) {
+  for EnemyKilledEvent {
+    position, color, ..
+  } in killed_events.read()
+  {
+    bursts.send(ParticleBurstEvent {
+      emitter: Emitter::Point(*position),
+      count: 24,
+      color: *color,
+      speed: 6.,
+      size: 0.12,
+      lifetime_secs: 0.6,
+    });
+  }
+}
+
+fn dash_trails(
+  mut swap_events: EventReader<SwapEvent>,
+  mut bursts: EventWriter<ParticleBurstEvent>,
+) {
+  for SwapEvent { from, to } in swap_events.read() {
+    bursts.send(ParticleBurstEvent {
+      emitter: Emitter::Line(*from, *to),
+      count: (from.distance(*to) * TRAIL_DENSITY) as usize,
+      color: PLAYER_COLOR.with_alpha(0.6),
+      speed: 0.5,
+      size: 0.08,
+      lifetime_secs: 0.4,
+    });
+  }
+}
+
+fn player_hit_sparks(
+  mut resolved_events: EventReader<ResolvedDamageEvent>,
+  players: Query<(), With<Player>>,
+  mut bursts: EventWriter<ParticleBurstEvent>,
+) {
+  for event in resolved_events.read() {
+    if !players.contains(event.target) {
+      continue;
+    }
+    bursts.send(ParticleBurstEvent {
+      emitter: Emitter::Point(event.position),
+      count: 12,
+      color: Color::srgb(1.0, 0.9, 0.5),
+      speed: 8.,
+      size: 0.06,
+      lifetime_secs: 0.3,
+    });
+  }
+}
+
+fn pickup_sparkles(
+  mut timer: ResMut<SparkleTimer>,
+  time: Res<Time>,
+  pickups: Query<(&Transform, &Pickup, &ViewVisibility)>,
+  mut bursts: EventWriter<ParticleBurstEvent>,
+) {
+  if !timer.0.tick(time.delta()).just_finished() {
+    return;
+  }
+  // Blinked-out pickups don't sparkle either.
+  for (transform, pickup, _) in pickups.iter().filter(|(_, _, visible)| visible.get()) {
+    bursts.send(ParticleBurstEvent {
+      emitter: Emitter::Point(transform.translation.xy()),
+      count: 2,
+      color: pickup.color(),
+      speed: 1.,
+      size: 0.05,
+      lifetime_secs: 0.5,
+    });
+  }
+}
+
+fn emit_particles(
+  mut bursts: EventReader<ParticleBurstEvent>,
+  mut pool: ResMut<ParticlePool>,
+  mut particles: Query<(&mut Particle, &mut Transform, &mut Visibility)>,
+) {
+  let mut rng = rand::thread_rng();
+  for burst in bursts.read() {
+    for i in 0..burst.count {
+      let Some(entity) = pool.free.pop() else {
+        return;
+      };
+      let Ok((mut particle, mut transform, mut visibility)) = particles.get_mut(entity) else {
+        continue;
+      };
+
+      let position = match burst.emitter {
+        Emitter::Point(position) => position,
+        Emitter::Line(from, to) => from.lerp(to, (i as f32 + 0.5) / burst.count as f32),
+      };
+      *particle = Particle {
+        velocity: Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(0.0..=burst.speed),
+        color: burst.color,
+        size: burst.size,
+        // Staggered a little, so a burst thins out instead of vanishing all at once.
+        lifetime: Timer::from_seconds(
+          burst.lifetime_secs * rng.gen_range(0.6..=1.0),
+          TimerMode::Once,
+        ),
+      };
+      *transform = Transform::from_translation(position.extend(2.0));
+      *visibility = Visibility::Inherited;
+    }
+  }
+}
+
+// Particles drift to a stop while shrinking and fading out, then go back in the pool.
+fn update_particles(
+  mut pool: ResMut<ParticlePool>,
+  mut particles: Query<(
+    Entity,
+    &mut Particle,
+    &mut Transform,
+    &mut Sprite,
+    &mut Visibility,
+  )>,
+  time: Res<Time>,
+) {
+  let dt = time.delta_secs();
+  for (entity, mut particle, mut transform, mut sprite, mut visibility) in particles.iter_mut() {
+    if *visibility == Visibility::Hidden {
+      continue;
+    }
+    if particle.lifetime.tick(time.delta()).finished() {
+      *visibility = Visibility::Hidden;
+      pool.free.push(entity);
+      continue;
+    }
+
+    transform.translation += (particle.velocity * dt).extend(0.);
+    particle.velocity *= 1. - (PARTICLE_DRAG * dt).min(1.);
+    let remaining = 1. - particle.lifetime.fraction();
+    sprite.color = particle
+      .color
+      .with_alpha(particle.color.alpha() * remaining);
+    sprite.custom_size = Some(Vec2::splat(particle.size * (0.5 + 0.5 * remaining)));
+  }
+}
+
+fn measure_particles(mut diagnostics: Diagnostics, pool: Res<ParticlePool>) {
+  diagnostics.add_measurement(&PARTICLES_LIVE, || (MAX_PARTICLES - pool.free.len()) as f64);
+}
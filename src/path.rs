@@ -1,13 +1,66 @@
+use std::{f32::consts::TAU, fmt};
+
 use bevy::{asset::RenderAssetUsages, prelude::*};
 
 pub struct Path {
   pub vertices: Vec<Vec2>,
   pub indices: Vec<[usize; 2]>,
+  // First vertex of the contour being drawn, which `close` joins back up to.
+  contour_start: usize,
+}
+
+// How a stroke fills in the outside of each corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+  // Extends both edges until they meet, falling back to a bevel for very sharp corners.
+  Miter,
+  // Cuts the corner off flat.
+  Bevel,
+  Round,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriangulationError {
+  // Usually means an outline crosses itself, or a hole crosses its outline.
+  NoEarFound { remaining: usize },
+  // A hole that isn't inside any outline.
+  HoleOutsideOutline,
+}
+
+impl fmt::Display for TriangulationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TriangulationError::NoEarFound { remaining } => write!(
+        f,
+        "failed to find an ear with {} vertices left, is the polygon self-intersecting?",
+        remaining
+      ),
+      TriangulationError::HoleOutsideOutline => write!(f, "hole is not inside any outline"),
+    }
+  }
 }
 
-pub enum WindDirection {
-  Clockwise,
-  CounterClockwise,
+impl std::error::Error for TriangulationError {}
+
+// How far a miter can reach past its corner, in half stroke widths, before it's beveled instead.
+const MITER_LIMIT: f32 = 4.0;
+// The most a round join turns through in one segment.
+const ROUND_JOIN_STEP: f32 = TAU / 32.0;
+
+// A run of connected edges, as drawn between one `move_to` and the next.
+struct Contour {
+  vertices: Vec<usize>,
+  closed: bool,
+}
+
+// What clipping a vertex off a polygon would do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Corner {
+  // Cuts off a triangle with nothing else inside it.
+  Ear,
+  // Lies on the line between its neighbours, so it can go without cutting off any area.
+  Flat,
+  Blocked,
 }
 
 impl Path {
@@ -15,10 +68,96 @@ impl Path {
     Self {
       vertices: Vec::new(),
       indices: Vec::new(),
+      contour_start: 0,
+    }
+  }
+
+  // The shapes below are closed, centered on the origin and wound counterclockwise.
+
+  pub fn polygon(points: impl IntoIterator<Item = Vec2>) -> Self {
+    let mut path = Self::new();
+    for (i, point) in points.into_iter().enumerate() {
+      match i {
+        0 => path.move_to(point),
+        _ => path.line_to(point),
+      }
+    }
+    path.close();
+    path
+  }
+
+  pub fn circle(radius: f32, num_segments: u32) -> Self {
+    Self::polygon(
+      (0..num_segments).map(|i| Vec2::from_angle(TAU * i as f32 / num_segments as f32) * radius),
+    )
+  }
+
+  // With a flat edge along the bottom, so triangles point up and squares sit square.
+  pub fn regular_polygon(radius: f32, sides: u32) -> Self {
+    let start_angle = -TAU / 4.0 + TAU / (2 * sides) as f32;
+    Self::polygon(
+      (0..sides).map(|i| Vec2::from_angle(start_angle + TAU * i as f32 / sides as f32) * radius),
+    )
+  }
+
+  pub fn rect(half_size: Vec2) -> Self {
+    Self::polygon([
+      Vec2::new(-half_size.x, -half_size.y),
+      Vec2::new(half_size.x, -half_size.y),
+      Vec2::new(half_size.x, half_size.y),
+      Vec2::new(-half_size.x, half_size.y),
+    ])
+  }
+
+  pub fn rounded_rect(half_size: Vec2, radius: f32, segments_per_corner: u32) -> Self {
+    let inset = half_size - Vec2::splat(radius);
+    let mut path = Self::new();
+    for (i, corner) in [
+      Vec2::new(inset.x, -inset.y),
+      inset,
+      Vec2::new(-inset.x, inset.y),
+      -inset,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+      let start_angle = TAU * (i as f32 - 1.0) / 4.0;
+      path.arc(
+        corner,
+        radius,
+        start_angle,
+        start_angle + TAU / 4.0,
+        segments_per_corner,
+      );
     }
+    path.close();
+    path
+  }
+
+  // The part of a circle below `fraction` of the way up, like a tank filled to that level.
+  // `num_segments` is for the whole circle, and the arc gets its share of them.
+  pub fn filled_circle(radius: f32, fraction: f32, num_segments: u32) -> Self {
+    // Where the fill level meets the right side of the circle.
+    let level_angle = (fraction.clamp(0.0, 1.0) * 2.0 - 1.0).asin();
+    let start_angle = TAU / 2.0 - level_angle;
+    let end_angle = TAU + level_angle;
+    let arc_segments = (num_segments as f32 * (end_angle - start_angle) / TAU).ceil() as u32;
+
+    let mut path = Self::new();
+    path.arc(
+      Vec2::ZERO,
+      radius,
+      start_angle,
+      end_angle,
+      arc_segments.max(1),
+    );
+    path.close();
+    path
   }
 
+  // Starts a new contour. Closed contours wound the opposite way to the largest one are holes.
   pub fn move_to(&mut self, pos: Vec2) {
+    self.contour_start = self.vertices.len();
     self.vertices.push(pos);
   }
 
@@ -28,41 +167,66 @@ impl Path {
     self.indices.push([index - 1, index]);
   }
 
-  pub fn arc_to(
+  pub fn quadratic_to(&mut self, control: Vec2, end_pos: Vec2, num_segments: u32) {
+    let start_pos = *self.vertices.last().unwrap();
+    for i in 1..=num_segments {
+      let t = i as f32 / num_segments as f32;
+      let pos = start_pos.lerp(control, t).lerp(control.lerp(end_pos, t), t);
+      self.line_to(pos);
+    }
+  }
+
+  pub fn cubic_to(&mut self, control1: Vec2, control2: Vec2, end_pos: Vec2, num_segments: u32) {
+    let start_pos = *self.vertices.last().unwrap();
+    for i in 1..=num_segments {
+      let t = i as f32 / num_segments as f32;
+      // De Casteljau: interpolate between neighbouring points until only one is left.
+      let a = start_pos.lerp(control1, t);
+      let b = control1.lerp(control2, t);
+      let c = control2.lerp(end_pos, t);
+      let pos = a.lerp(b, t).lerp(b.lerp(c, t), t);
+      self.line_to(pos);
+    }
+  }
+
+  // An arc around `center` between two angles, in radians counterclockwise from +x. Sweeps
+  // counterclockwise if `end_angle` is the larger one, clockwise otherwise. Starts the path if it's
+  // empty, and otherwise lines up to the start of the arc if the path isn't already there.
+  pub fn arc(
     &mut self,
-    end_pos: Vec2,
-    arc_center: Vec2,
+    center: Vec2,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
     num_segments: u32,
-    direction: WindDirection,
   ) {
-    let start_pos = self.vertices.last().unwrap();
-    let radius = (start_pos - arc_center).length();
-    let start_angle = f32::atan2(start_pos.y - arc_center.y, start_pos.x - arc_center.x);
-    let end_angle = f32::atan2(end_pos.y - arc_center.y, end_pos.x - arc_center.x);
-    let unclamped_sweep_angle = end_angle - start_angle;
-    let sweep_angle = match direction {
-      WindDirection::Clockwise => {
-        unclamped_sweep_angle.rem_euclid(std::f32::consts::PI * 2.0) - std::f32::consts::PI * 2.0
-      }
-      WindDirection::CounterClockwise => {
-        unclamped_sweep_angle.rem_euclid(std::f32::consts::PI * 2.0)
-      }
-    };
-    let angle_step = sweep_angle / num_segments as f32;
+    let start_pos = center + Vec2::from_angle(start_angle) * radius;
+    match self.vertices.last() {
+      None => self.move_to(start_pos),
+      Some(last) if !last.abs_diff_eq(start_pos, 1e-6) => self.line_to(start_pos),
+      Some(_) => {}
+    }
 
+    let angle_step = (end_angle - start_angle) / num_segments as f32;
     for i in 1..=num_segments {
       let angle = start_angle + i as f32 * angle_step;
-      let pos = arc_center + Vec2::new(angle.cos(), angle.sin()) * radius;
-      self.line_to(pos);
+      self.line_to(center + Vec2::from_angle(angle) * radius);
     }
   }
 
-  // TODO write a variant that takes a start angle and end angle.
-  // fn arc_to(&mut self, end_pos: Vec2, start_angle: f32, end_angle: f32, num_segments: u32) {
-
   pub fn close(&mut self) {
     let index = self.vertices.len();
-    self.indices.push([index - 1, 0]);
+    self.indices.push([index - 1, self.contour_start]);
+  }
+
+  // Adds the other path's contours to this one as they are, so a clockwise one becomes a hole.
+  pub fn append(&mut self, other: &Path) {
+    let offset = self.vertices.len();
+    self.vertices.extend_from_slice(&other.vertices);
+    self
+      .indices
+      .extend(other.indices.iter().map(|[a, b]| [a + offset, b + offset]));
+    self.contour_start = offset + other.contour_start;
   }
 
   pub fn reverse_winding_order(&mut self) {
@@ -104,98 +268,412 @@ impl Path {
     mesh
   }
 
-  pub fn build_triangle_mesh(&self) -> Mesh {
-    let triangles = self.triangulate();
+  pub fn build_triangle_mesh(&self) -> Result<Mesh, TriangulationError> {
+    let triangles = self.triangulate()?;
+    Ok(triangle_list_mesh(
+      triangles.iter().flatten().map(|i| self.vertices[*i]),
+    ))
+  }
 
-    let mut mesh = Mesh::new(
-      bevy::render::render_resource::PrimitiveTopology::TriangleList,
-      RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
-    );
-    mesh.insert_attribute(
-      Mesh::ATTRIBUTE_POSITION,
-      triangles
+  // A band of the given width centered on every contour, joined at corners as asked. Open contours
+  // end flush with their first and last vertices.
+  pub fn build_stroke_mesh(&self, width: f32, join: LineJoin) -> Mesh {
+    let half_width = width / 2.0;
+    let mut positions = Vec::new();
+    for contour in self.contours() {
+      let mut points = contour
+        .vertices
         .iter()
-        .flatten()
         .map(|i| self.vertices[*i])
-        // Must convert to Vec3 because Mesh::ATTRIBUTE_POSITION is Vec3.
-        .map(|v| Vec3::new(v.x, v.y, 0.0))
-        .collect::<Vec<_>>(),
-    );
+        .collect::<Vec<_>>();
+      // Zero length edges have no direction to offset along.
+      points.dedup_by(|a, b| a.abs_diff_eq(*b, 1e-6));
+      if contour.closed && points.len() > 1 && points[0].abs_diff_eq(points[points.len() - 1], 1e-6)
+      {
+        points.pop();
+      }
+      if points.len() < 2 {
+        continue;
+      }
 
-    mesh
+      let edge_count = match contour.closed {
+        true => points.len(),
+        false => points.len() - 1,
+      };
+      let point = |i: usize| points[i % points.len()];
+      for i in 0..edge_count {
+        let (start, end) = (point(i), point(i + 1));
+        let normal = (end - start).perp().normalize() * half_width;
+        positions.extend([
+          start - normal,
+          end - normal,
+          end + normal,
+          start - normal,
+          end + normal,
+          start + normal,
+        ]);
+      }
+      // Every edge meets the next one, except the last edge of an open contour.
+      for i in 0..edge_count - usize::from(!contour.closed) {
+        push_join(
+          &mut positions,
+          [point(i), point(i + 1), point(i + 2)],
+          half_width,
+          join,
+        );
+      }
+    }
+    triangle_list_mesh(positions)
   }
 
-  fn triangulate(&self) -> Vec<[usize; 3]> {
-    // O(n^3) algorithm for triangulating a polygon.
-    // https://en.wikipedia.org/wiki/Polygon_triangulation#Ear_clipping_method
-    // Could be optimized to O(n^2) by more intelligently searching for ears.
-    let mut triangles: Vec<[usize; 3]> = Vec::new();
-    let mut remaining_vertex_indices = self
-      .indices
+  // Triangles covering every closed contour wound the same way as the largest one, minus holes cut
+  // by contours wound the other way. Open contours enclose nothing, so they're left out.
+  pub fn triangulate(&self) -> Result<Vec<[usize; 3]>, TriangulationError> {
+    let rings = self
+      .contours()
+      .into_iter()
+      .filter(|contour| contour.closed && contour.vertices.len() >= 3)
+      .map(|contour| (self.signed_area(&contour.vertices), contour.vertices))
+      .filter(|(area, _)| *area != 0.0)
+      .collect::<Vec<_>>();
+    let Some(largest) = rings
       .iter()
-      .map(|[a, _]| *a)
-      // .chain(std::iter::once(0))
+      .map(|(area, _)| *area)
+      .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+    else {
+      return Ok(Vec::new());
+    };
+
+    // Outlines get clipped counterclockwise and holes get joined in clockwise, however they were
+    // drawn.
+    let mut outlines = Vec::new();
+    let mut holes = Vec::new();
+    for (area, mut ring) in rings {
+      let is_outline = (area > 0.0) == (largest > 0.0);
+      if (area > 0.0) != is_outline {
+        ring.reverse();
+      }
+      match is_outline {
+        true => outlines.push((area.abs(), ring)),
+        false => holes.push(ring),
+      }
+    }
+
+    // Right to left, so each bridge only has to get past holes that are already part of the outline.
+    holes.sort_by(|a, b| {
+      let rightmost = |ring: &[usize]| self.vertices[ring[self.rightmost(ring)]].x;
+      rightmost(b).total_cmp(&rightmost(a))
+    });
+    for hole in holes {
+      let pos = self.vertices[hole[self.rightmost(&hole)]];
+      // The innermost outline around it, in case outlines are nested inside other holes.
+      let (_, outline) = outlines
+        .iter_mut()
+        .filter(|(_, outline)| self.contains(outline, pos))
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .ok_or(TriangulationError::HoleOutsideOutline)?;
+      self.bridge_hole(outline, &hole)?;
+    }
+
+    let mut triangles = Vec::new();
+    for (_, outline) in outlines {
+      self.clip_ears(&outline, &mut triangles)?;
+    }
+    Ok(triangles)
+  }
+
+  // Splits the edges back up into the contours they were drawn as.
+  fn contours(&self) -> Vec<Contour> {
+    let mut contours: Vec<Contour> = Vec::new();
+    for &[a, b] in self.indices.iter() {
+      match contours.last_mut() {
+        Some(contour) if !contour.closed && contour.vertices.last() == Some(&a) => {
+          if contour.vertices[0] == b {
+            contour.closed = true;
+          } else {
+            contour.vertices.push(b);
+          }
+        }
+        _ => contours.push(Contour {
+          vertices: vec![a, b],
+          closed: false,
+        }),
+      }
+    }
+    contours
+  }
+
+  // Positive for counterclockwise rings.
+  fn signed_area(&self, ring: &[usize]) -> f32 {
+    (0..ring.len())
+      .map(|i| {
+        let a = self.vertices[ring[i]];
+        let b = self.vertices[ring[(i + 1) % ring.len()]];
+        a.perp_dot(b)
+      })
+      .sum::<f32>()
+      / 2.0
+  }
+
+  // Position in the ring of its vertex furthest along +x.
+  fn rightmost(&self, ring: &[usize]) -> usize {
+    (0..ring.len())
+      .max_by(|a, b| {
+        self.vertices[ring[*a]]
+          .x
+          .total_cmp(&self.vertices[ring[*b]].x)
+      })
+      .unwrap()
+  }
+
+  // Even-odd rule, counting the ring's edges crossed on the way out to +x.
+  fn contains(&self, ring: &[usize], pos: Vec2) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+      let a = self.vertices[ring[i]];
+      let b = self.vertices[ring[(i + 1) % ring.len()]];
+      if (a.y > pos.y) != (b.y > pos.y) && pos.x < a.x + (pos.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+        inside = !inside;
+      }
+    }
+    inside
+  }
+
+  // Cuts a channel from the hole's rightmost vertex to an outline vertex it can see, so the outline
+  // runs in along the channel, around the hole and back out again as a single ring.
+  // https://www.geometrictools.com/Documentation/TriangulationByEarClipping.pdf
+  fn bridge_hole(
+    &self,
+    outline: &mut Vec<usize>,
+    hole: &[usize],
+  ) -> Result<(), TriangulationError> {
+    let hole_start = self.rightmost(hole);
+    let from = self.vertices[hole[hole_start]];
+
+    // The closest outline edge straight out to the right, and the end of it further right.
+    let mut nearest: Option<(f32, usize)> = None;
+    for i in 0..outline.len() {
+      let j = (i + 1) % outline.len();
+      let (a, b) = (self.vertices[outline[i]], self.vertices[outline[j]]);
+      // Horizontal edges are crossed at their ends, which the edges either side pick up.
+      if a.y == b.y || from.y < a.y.min(b.y) || from.y > a.y.max(b.y) {
+        continue;
+      }
+      let x = a.x + (from.y - a.y) * (b.x - a.x) / (b.y - a.y);
+      if x < from.x || nearest.is_some_and(|(nearest_x, _)| nearest_x <= x) {
+        continue;
+      }
+      let end = match (from.y == a.y, from.y == b.y) {
+        (true, _) => i,
+        (_, true) => j,
+        _ if a.x > b.x => i,
+        _ => j,
+      };
+      nearest = Some((x, end));
+    }
+    let (x, mut bridge) = nearest.ok_or(TriangulationError::HoleOutsideOutline)?;
+
+    // Unless the ray hit a vertex dead on, parts of the outline can poke into the triangle between
+    // the hole and that end of the edge, hiding it. Then the reflex vertex closest in angle to the
+    // ray is visible instead.
+    let hit = Vec2::new(x, from.y);
+    let end = self.vertices[outline[bridge]];
+    if end != hit {
+      let mut best = (f32::INFINITY, f32::INFINITY);
+      for i in 0..outline.len() {
+        let pos = self.vertices[outline[i]];
+        let prev = self.vertices[outline[(i + outline.len() - 1) % outline.len()]];
+        let next = self.vertices[outline[(i + 1) % outline.len()]];
+        if pos == end || sign(prev, pos, next) >= 0.0 || !is_point_in_triangle(pos, from, hit, end)
+        {
+          continue;
+        }
+        let offset = pos - from;
+        let candidate = (offset.y.abs().atan2(offset.x), offset.length_squared());
+        if candidate < best {
+          best = candidate;
+          bridge = i;
+        }
+      }
+    }
+
+    let mut bridged = Vec::with_capacity(outline.len() + hole.len() + 2);
+    bridged.extend_from_slice(&outline[..=bridge]);
+    bridged.extend(hole[hole_start..].iter().chain(&hole[..=hole_start]));
+    bridged.extend_from_slice(&outline[bridge..]);
+    *outline = bridged;
+    Ok(())
+  }
+
+  // O(n^2) ear clipping of a counterclockwise ring.
+  // https://en.wikipedia.org/wiki/Polygon_triangulation#Ear_clipping_method
+  // Clipping a vertex can only change whether its two neighbours are ears, so that's cached per
+  // vertex and only rechecked for those.
+  fn clip_ears(
+    &self,
+    ring: &[usize],
+    triangles: &mut Vec<[usize; 3]>,
+  ) -> Result<(), TriangulationError> {
+    let count = ring.len();
+    let mut prev = (0..count)
+      .map(|i| (i + count - 1) % count)
+      .collect::<Vec<_>>();
+    let mut next = (0..count).map(|i| (i + 1) % count).collect::<Vec<_>>();
+    let mut corners = (0..count)
+      .map(|i| self.corner(ring, &prev, &next, i))
       .collect::<Vec<_>>();
 
-    while remaining_vertex_indices.len() >= 3 {
-      // Find and remove one ear.
-      let mut found_ear = false;
-      for index_index in 0..remaining_vertex_indices.len() {
-        let prev_index_index =
-          (index_index + remaining_vertex_indices.len() - 1) % remaining_vertex_indices.len();
-        let next_index_index = (index_index + 1) % remaining_vertex_indices.len();
-        // O(n). Could be cached but would have to invalidate it when removing adjacent vertices.
-        if is_ear(
-          self,
-          remaining_vertex_indices[index_index],
-          remaining_vertex_indices[prev_index_index],
-          remaining_vertex_indices[next_index_index],
-        ) {
-          // Emit a triangle: (vertex.prev, ear, vertex.next)
-          triangles.push([
-            remaining_vertex_indices[prev_index_index],
-            remaining_vertex_indices[index_index],
-            remaining_vertex_indices[next_index_index],
-          ]);
-          // Delete ear from the vertex list, leaving us with a smaller polygon.
-          remaining_vertex_indices.remove(index_index);
-          found_ear = true;
-          break;
+    let mut remaining = count;
+    let mut current = 0;
+    let mut checked = 0;
+    // Degenerate rings, like ones with holes bridged in, can bend the rules that keep the cache
+    // right. So before giving up, everything gets rechecked once.
+    let mut rechecked = false;
+    while remaining > 3 {
+      if corners[current] == Corner::Blocked {
+        current = next[current];
+        checked += 1;
+        if checked < remaining {
+          continue;
+        }
+        if rechecked {
+          return Err(TriangulationError::NoEarFound { remaining });
         }
+        for _ in 0..remaining {
+          corners[current] = self.corner(ring, &prev, &next, current);
+          current = next[current];
+        }
+        rechecked = true;
+        checked = 0;
+        continue;
       }
-      assert!(
-        found_ear,
-        "Failed to find an ear, is the polygon self-intersecting?"
-      );
+
+      let (before, after) = (prev[current], next[current]);
+      if corners[current] == Corner::Ear {
+        triangles.push([ring[before], ring[current], ring[after]]);
+      }
+      next[before] = after;
+      prev[after] = before;
+      remaining -= 1;
+      corners[before] = self.corner(ring, &prev, &next, before);
+      corners[after] = self.corner(ring, &prev, &next, after);
+      current = before;
+      checked = 0;
+      rechecked = false;
     }
 
-    triangles
+    let (before, after) = (prev[current], next[current]);
+    let [a, b, c] = [before, current, after].map(|i| self.vertices[ring[i]]);
+    if sign(a, b, c) > 0.0 {
+      triangles.push([ring[before], ring[current], ring[after]]);
+    }
+    Ok(())
+  }
+
+  fn corner(&self, ring: &[usize], prev: &[usize], next: &[usize], i: usize) -> Corner {
+    let pos = |i: usize| self.vertices[ring[i]];
+    let (a, b, c) = (pos(prev[i]), pos(i), pos(next[i]));
+    let turn = sign(a, b, c);
+    if turn == 0.0 {
+      return Corner::Flat;
+    }
+    // Clockwise turns would cut off a triangle outside the polygon.
+    if turn < 0.0 {
+      return Corner::Blocked;
+    }
+
+    // Only reflex vertices can poke into an ear. Ones sharing a position with a corner, like the
+    // two ends of a bridge, can't be inside it.
+    let mut other = next[next[i]];
+    while other != prev[i] {
+      let p = pos(other);
+      if p != a
+        && p != b
+        && p != c
+        && sign(pos(prev[other]), p, pos(next[other])) < 0.0
+        && is_point_in_triangle(p, a, b, c)
+      {
+        return Corner::Blocked;
+      }
+      other = next[other];
+    }
+    Corner::Ear
   }
 }
 
-fn is_ear(path: &Path, ear: usize, prev: usize, next: usize) -> bool {
-  let ear_pos = path.vertices[ear];
-  let prev_pos = path.vertices[prev];
-  let next_pos = path.vertices[next];
+fn triangle_list_mesh(positions: impl IntoIterator<Item = Vec2>) -> Mesh {
+  let mut mesh = Mesh::new(
+    bevy::render::render_resource::PrimitiveTopology::TriangleList,
+    RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+  );
+  mesh.insert_attribute(
+    Mesh::ATTRIBUTE_POSITION,
+    positions
+      .into_iter()
+      // Must convert to Vec3 because Mesh::ATTRIBUTE_POSITION is Vec3.
+      .map(|v| Vec3::new(v.x, v.y, 0.0))
+      .collect::<Vec<_>>(),
+  );
 
-  // Verify that the triangle is counter-clockwise oriented (i.e. is inside the polygon, a 'front face').
-  if sign(prev_pos, ear_pos, next_pos) <= 0.0 {
-    return false;
+  mesh
+}
+
+// Fills the gap the two edges' bands leave on the outside of the corner.
+fn push_join(
+  positions: &mut Vec<Vec2>,
+  [prev, corner, next]: [Vec2; 3],
+  half_width: f32,
+  join: LineJoin,
+) {
+  let dir_in = (corner - prev).normalize();
+  let dir_out = (next - corner).normalize();
+  let turn = dir_in.perp_dot(dir_out);
+  // Straight on, so the bands already meet.
+  if turn.abs() < 1e-6 && dir_in.dot(dir_out) > 0.0 {
+    return;
   }
 
-  // Verify there are no other vertices inside the triangle.
-  for i in 0..path.vertices.len() {
-    if i == ear || i == prev || i == next {
-      continue;
+  // Turning left leaves the gap on the right, and the other way around.
+  let side = match turn > 0.0 {
+    true => -half_width,
+    false => half_width,
+  };
+  let from = corner + dir_in.perp() * side;
+  let to = corner + dir_out.perp() * side;
+  match join {
+    LineJoin::Miter => {
+      let miter = (from + to - 2.0 * corner).normalize_or_zero();
+      // Cosine of half the angle between the two offsets.
+      let cos_half = miter.dot(from - corner) / half_width;
+      if cos_half > 1.0 / MITER_LIMIT {
+        let tip = corner + miter * half_width / cos_half;
+        push_triangle(positions, corner, from, tip);
+        push_triangle(positions, corner, tip, to);
+      } else {
+        push_triangle(positions, corner, from, to);
+      }
     }
-
-    let pos = path.vertices[i];
-    if is_point_in_triangle(pos, prev_pos, ear_pos, next_pos) {
-      return false;
+    LineJoin::Bevel => push_triangle(positions, corner, from, to),
+    LineJoin::Round => {
+      let offset = from - corner;
+      let sweep = offset.angle_to(to - corner);
+      let steps = (sweep.abs() / ROUND_JOIN_STEP).ceil().max(1.0) as u32;
+      let mut last = from;
+      for i in 1..=steps {
+        let pos = corner + Vec2::from_angle(sweep * i as f32 / steps as f32).rotate(offset);
+        push_triangle(positions, corner, last, pos);
+        last = pos;
+      }
     }
   }
+}
 
-  true
+// Counterclockwise whichever order the corners come in, so it faces the camera.
+fn push_triangle(positions: &mut Vec<Vec2>, a: Vec2, b: Vec2, c: Vec2) {
+  match sign(a, b, c) >= 0.0 {
+    true => positions.extend([a, b, c]),
+    false => positions.extend([a, c, b]),
+  }
 }
 
 fn sign(p1: Vec2, p2: Vec2, p3: Vec2) -> f32 {
@@ -222,7 +700,247 @@ fn is_point_in_triangle(point: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
     return true;
   }
 
-  // If we're here, we know the point is collinear with all three edges.
-  // For now, just return false.
-  false
+  // If we're here, the triangle is flat and the point lies on the same line. It's inside if it's on
+  // the stretch of line the triangle covers.
+  let min = a.min(b).min(c);
+  let max = a.max(b).max(c);
+  point.cmpge(min).all() && point.cmple(max).all()
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::render::mesh::VertexAttributeValues;
+  use rand::{rngs::StdRng, Rng, SeedableRng};
+
+  use super::*;
+
+  const CASES: u64 = 200;
+
+  // Signed area straight from the edges, independent of the triangulation.
+  fn path_area(path: &Path) -> f32 {
+    path
+      .indices
+      .iter()
+      .map(|[a, b]| path.vertices[*a].perp_dot(path.vertices[*b]))
+      .sum::<f32>()
+      / 2.0
+  }
+
+  fn triangle_areas(path: &Path, triangles: &[[usize; 3]]) -> Vec<f32> {
+    triangles
+      .iter()
+      .map(|[a, b, c]| sign(path.vertices[*a], path.vertices[*b], path.vertices[*c]) / 2.0)
+      .collect()
+  }
+
+  // Vertices at jittered, evenly spread angles around the origin, each at a random distance. No gap
+  // between angles reaches half a turn, so the outline can't cross itself.
+  fn random_simple_polygon(rng: &mut StdRng, sides: u32, radii: std::ops::Range<f32>) -> Path {
+    Path::polygon((0..sides).map(|i| {
+      let angle = (i as f32 + rng.gen_range(0.0..0.9)) * TAU / sides as f32;
+      Vec2::from_angle(angle) * rng.gen_range(radii.clone())
+    }))
+  }
+
+  fn assert_covers(path: &Path, expected_area: f32) {
+    let triangles = path.triangulate().expect("simple polygons triangulate");
+    let areas = triangle_areas(path, &triangles);
+    assert!(
+      areas.iter().all(|area| *area >= 0.0),
+      "every triangle faces the camera: {:?}",
+      areas
+    );
+    let area = areas.iter().sum::<f32>();
+    assert!(
+      (area - expected_area).abs() <= 1e-4 * expected_area.max(1.0),
+      "triangles cover {} but the polygon covers {}",
+      area,
+      expected_area
+    );
+  }
+
+  #[test]
+  fn triangulated_area_matches_polygon_area() {
+    for seed in 0..CASES {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let sides = rng.gen_range(4..40);
+      let path = random_simple_polygon(&mut rng, sides, 0.1..1.0);
+      assert_covers(&path, path_area(&path));
+    }
+  }
+
+  #[test]
+  fn clockwise_polygons_triangulate_counterclockwise() {
+    for seed in 0..CASES {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let sides = rng.gen_range(4..40);
+      let mut path = random_simple_polygon(&mut rng, sides, 0.1..1.0);
+      let area = path_area(&path);
+      path.reverse_winding_order();
+      assert!(path_area(&path) < 0.0);
+      assert_covers(&path, area);
+    }
+  }
+
+  #[test]
+  fn reverse_winding_order_round_trips() {
+    for seed in 0..CASES {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let sides = rng.gen_range(4..40);
+      let mut path = random_simple_polygon(&mut rng, sides, 0.1..1.0);
+      let indices = path.indices.clone();
+      path.reverse_winding_order();
+      assert_ne!(path.indices, indices);
+      path.reverse_winding_order();
+      assert_eq!(path.indices, indices);
+    }
+  }
+
+  #[test]
+  fn holes_are_left_uncovered() {
+    for seed in 0..CASES {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let sides = rng.gen_range(8..40);
+      // With at least 8 sides, the outline stays further out than any vertex of the hole.
+      let mut path = random_simple_polygon(&mut rng, sides, 0.5..1.0);
+      let hole_sides = rng.gen_range(4..20);
+      let mut hole = random_simple_polygon(&mut rng, hole_sides, 0.05..0.3);
+      hole.reverse_winding_order();
+      path.append(&hole);
+      assert_covers(&path, path_area(&path));
+    }
+  }
+
+  #[test]
+  fn collinear_and_repeated_vertices_are_skipped() {
+    let path = Path::polygon([
+      Vec2::new(0.0, 0.0),
+      Vec2::new(1.0, 0.0),
+      Vec2::new(2.0, 0.0),
+      Vec2::new(2.0, 0.0),
+      Vec2::new(2.0, 2.0),
+      Vec2::new(1.0, 1.0),
+      Vec2::new(0.0, 2.0),
+    ]);
+    assert_covers(&path, 3.0);
+  }
+
+  #[test]
+  fn holes_outside_every_outline_are_errors() {
+    let mut path = Path::rect(Vec2::ONE);
+    let mut hole = Path::polygon([
+      Vec2::new(3.0, 0.0),
+      Vec2::new(4.0, 0.0),
+      Vec2::new(4.0, 1.0),
+    ]);
+    hole.reverse_winding_order();
+    path.append(&hole);
+    assert_eq!(
+      path.triangulate(),
+      Err(TriangulationError::HoleOutsideOutline)
+    );
+  }
+
+  #[test]
+  fn points_in_flat_triangles() {
+    let (a, b, c) = (Vec2::ZERO, Vec2::new(1.0, 1.0), Vec2::new(2.0, 2.0));
+    assert!(is_point_in_triangle(Vec2::new(0.5, 0.5), a, b, c));
+    assert!(is_point_in_triangle(Vec2::new(2.0, 2.0), a, c, b));
+    assert!(!is_point_in_triangle(Vec2::new(3.0, 3.0), a, b, c));
+    assert!(!is_point_in_triangle(Vec2::new(-0.5, -0.5), a, b, c));
+  }
+
+  #[test]
+  fn points_on_triangle_edges_are_inside() {
+    let (a, b, c) = (Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(0.0, 2.0));
+    assert!(is_point_in_triangle(Vec2::new(1.0, 0.0), a, b, c));
+    assert!(is_point_in_triangle(Vec2::new(1.0, 1.0), a, b, c));
+    assert!(is_point_in_triangle(Vec2::new(0.5, 0.5), a, b, c));
+    assert!(!is_point_in_triangle(Vec2::new(1.5, 1.5), a, b, c));
+  }
+
+  #[test]
+  fn quadratic_curves_pass_through_their_midpoint() {
+    let mut path = Path::new();
+    path.move_to(Vec2::ZERO);
+    path.quadratic_to(Vec2::new(1.0, 2.0), Vec2::new(2.0, 0.0), 2);
+    assert_eq!(path.vertices.len(), 3);
+    assert!(path.vertices[1].abs_diff_eq(Vec2::new(1.0, 1.0), 1e-6));
+    assert!(path.vertices[2].abs_diff_eq(Vec2::new(2.0, 0.0), 1e-6));
+  }
+
+  #[test]
+  fn cubic_curves_pass_through_their_midpoint() {
+    let mut path = Path::new();
+    path.move_to(Vec2::ZERO);
+    path.cubic_to(
+      Vec2::new(0.0, 1.0),
+      Vec2::new(1.0, 1.0),
+      Vec2::new(1.0, 0.0),
+      2,
+    );
+    assert_eq!(path.vertices.len(), 3);
+    assert!(path.vertices[1].abs_diff_eq(Vec2::new(0.5, 0.75), 1e-6));
+    assert!(path.vertices[2].abs_diff_eq(Vec2::new(1.0, 0.0), 1e-6));
+  }
+
+  #[test]
+  fn arcs_sweep_toward_the_end_angle() {
+    let wedge = |start_angle, end_angle| {
+      let mut path = Path::new();
+      path.move_to(Vec2::ZERO);
+      path.arc(Vec2::ZERO, 1.0, start_angle, end_angle, 8);
+      path.close();
+      path
+    };
+    let counterclockwise = wedge(0.0, TAU / 4.0);
+    assert!(path_area(&counterclockwise) > 0.0);
+    assert!(counterclockwise.vertices[5].y > 0.0);
+    let clockwise = wedge(TAU / 4.0, 0.0);
+    assert!(path_area(&clockwise) < 0.0);
+    assert!(clockwise
+      .vertices
+      .last()
+      .unwrap()
+      .abs_diff_eq(Vec2::X, 1e-6));
+  }
+
+  #[test]
+  fn stroke_area_depends_on_the_join() {
+    let stroke_area = |join| {
+      let mesh = Path::rect(Vec2::ONE).build_stroke_mesh(0.2, join);
+      let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+      else {
+        panic!("stroke meshes have positions");
+      };
+      let areas = positions
+        .chunks(3)
+        .map(|triangle| {
+          let [a, b, c] = [0, 1, 2].map(|i| Vec2::new(triangle[i][0], triangle[i][1]));
+          sign(a, b, c) / 2.0
+        })
+        .collect::<Vec<_>>();
+      assert!(areas.iter().all(|area| *area >= 0.0));
+      areas.iter().sum::<f32>()
+    };
+    // Four 2 by 0.2 bands, overlapping at the inside of each corner, plus what each join adds on
+    // the outside: a 0.1 square, half of one, or a quarter circle cut into 8 slices.
+    let bands = 4.0 * 2.0 * 0.2;
+    let round = 4.0 * 8.0 * 0.5 * 0.01 * (TAU / 32.0).sin();
+    for (join, expected) in [
+      (LineJoin::Miter, bands + 0.04),
+      (LineJoin::Bevel, bands + 0.02),
+      (LineJoin::Round, bands + round),
+    ] {
+      let area = stroke_area(join);
+      assert!(
+        (area - expected).abs() < 1e-4,
+        "{:?} covers {} instead of {}",
+        join,
+        area,
+        expected
+      );
+    }
+  }
 }
@@ -0,0 +1,382 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+  collision::{Collider, CollisionEvent, CollisionLayer, DetectCollisionsSet},
+  damage::{ApplyDamageSet, DamageEvent, DamageSource, Invulnerable},
+  path::{LineJoin, Path},
+  playing::{Enemy, EnemyKilledEvent, Player},
+  window_setup::PlayArea,
+  AppState, EnableStateScopedResource, MOUSE_RADIUS,
+};
+
+pub struct PickupsPlugin;
+
+impl Plugin for PickupsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .enable_state_scoped_resource::<PowerUps>(AppState::Playing)
+      .add_event::<BombPickupEvent>()
+      .add_systems(Startup, build_pickup_visuals)
+      .add_systems(OnEnter(AppState::Playing), init_resources)
+      .add_systems(
+        Update,
+        (
+          drop_pickups,
+          (collect_pickups, detonate_bomb_pickups)
+            .chain()
+            .after(DetectCollisionsSet)
+            .before(ApplyDamageSet),
+          (expire_pickups, blink_expiring_pickups).chain(),
+          tick_power_ups,
+          (show_shield_bubbles, tick_shields).chain(),
+        )
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+// Chance that a killed enemy drops something.
+const DROP_CHANCE: f64 = 0.15;
+const PICKUP_RADIUS: f32 = 0.2;
+const PICKUP_LIFETIME_SECS: f32 = 8.0;
+// Pickups blink for this long before they disappear.
+const PICKUP_BLINK_SECS: f32 = 2.0;
+const PICKUP_BLINKS_PER_SEC: f32 = 6.0;
+const PICKUP_OUTLINE_WIDTH: f32 = 0.05;
+
+const HEALTH_RESTORED: u32 = 10;
+const SHIELD_SECS: f32 = 3.0;
+const SHIELD_BUBBLE_RADIUS: f32 = MOUSE_RADIUS + 0.12;
+const SHIELD_BUBBLE_WIDTH: f32 = 0.04;
+const DASH_BOOST_SECS: f32 = 5.0;
+const DASH_BOOST_DAMAGE_MULTIPLIER: u32 = 2;
+const SWAP_HASTE_SECS: f32 = 5.0;
+// Swap timers tick this much faster while hasted.
+const SWAP_HASTE_SPEED: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PickupKind {
+  Health,
+  DashBoost,
+  SwapHaste,
+  Shield,
+  Bomb,
+}
+
+impl PickupKind {
+  // Relative drop weights.
+  const DROP_TABLE: [(PickupKind, u32); 5] = [
+    (PickupKind::Health, 4),
+    (PickupKind::DashBoost, 3),
+    (PickupKind::SwapHaste, 3),
+    (PickupKind::Shield, 2),
+    (PickupKind::Bomb, 1),
+  ];
+
+  fn random(rng: &mut impl Rng) -> Self {
+    let total: u32 = Self::DROP_TABLE.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.gen_range(0..total);
+    for (kind, weight) in Self::DROP_TABLE {
+      if roll < weight {
+        return kind;
+      }
+      roll -= weight;
+    }
+    unreachable!("roll is less than the total weight")
+  }
+
+  fn color(self) -> Color {
+    match self {
+      PickupKind::Health => Color::hsl(120., 0.8, 0.6),
+      PickupKind::DashBoost => Color::hsl(0., 0.95, 0.6),
+      PickupKind::SwapHaste => Color::hsl(200., 0.95, 0.65),
+      PickupKind::Shield => Color::hsl(50., 0.95, 0.65),
+      PickupKind::Bomb => Color::WHITE,
+    }
+  }
+
+  // Health is round, everything else gets more corners the rarer it is.
+  fn sides(self) -> u32 {
+    match self {
+      PickupKind::Health => 12,
+      PickupKind::DashBoost => 3,
+      PickupKind::SwapHaste => 4,
+      PickupKind::Shield => 5,
+      PickupKind::Bomb => 6,
+    }
+  }
+}
+
+#[derive(Component, Debug)]
+pub struct Pickup {
+  kind: PickupKind,
+  lifetime: Timer,
+}
+
+impl Pickup {
+  pub fn color(&self) -> Color {
+    self.kind.color()
+  }
+}
+
+// A translucent fill with a solid outline on a child, shared by every pickup of a kind.
+struct PickupVisual {
+  fill: Handle<Mesh>,
+  fill_material: Handle<ColorMaterial>,
+  outline: Handle<Mesh>,
+  outline_material: Handle<ColorMaterial>,
+}
+
+#[derive(Resource)]
+struct PickupVisuals(HashMap<PickupKind, PickupVisual>);
+
+// Sent when the player collects a bomb, which then goes off.
+#[derive(Event, Debug)]
+pub struct BombPickupEvent {
+  pub pickup: Entity,
+}
+
+#[derive(Resource)]
+struct ShieldBubbleVisual {
+  mesh: Handle<Mesh>,
+  material: Handle<ColorMaterial>,
+}
+
+// The shield pickup's invulnerability, shown as a bubble around the player rather than the flashing
+// that follows a hit. Lasts as long as the Invulnerable it comes with.
+#[derive(Component, Debug)]
+pub struct Shield(Timer);
+
+#[derive(Component)]
+struct ShieldBubble;
+
+// Timed effects from pickups. Each runs out on its own.
+#[derive(Resource, Debug, Default)]
+pub struct PowerUps {
+  dash_boost: Duration,
+  swap_haste: Duration,
+}
+
+impl PowerUps {
+  pub fn dash_damage_multiplier(&self) -> u32 {
+    match self.dash_boost.is_zero() {
+      true => 1,
+      false => DASH_BOOST_DAMAGE_MULTIPLIER,
+    }
+  }
+
+  // How fast swap timers should tick, relative to real time.
+  pub fn swap_speed(&self) -> f32 {
+    match self.swap_haste.is_zero() {
+      true => 1.,
+      false => SWAP_HASTE_SPEED,
+    }
+  }
+}
+
+fn init_resources(mut commands: Commands) {
+  commands.insert_resource(PowerUps::default());
+}
+
+fn build_pickup_visuals(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+  let visuals = PickupKind::DROP_TABLE
+    .iter()
+    .map(|(kind, _)| {
+      let shape = Path::regular_polygon(PICKUP_RADIUS, kind.sides());
+      let visual = PickupVisual {
+        fill: meshes.add(
+          shape
+            .build_triangle_mesh()
+            .expect("regular polygons are simple polygons"),
+        ),
+        fill_material: materials.add(kind.color().with_alpha(0.4)),
+        outline: meshes.add(shape.build_stroke_mesh(PICKUP_OUTLINE_WIDTH, LineJoin::Miter)),
+        outline_material: materials.add(kind.color()),
+      };
+      (*kind, visual)
+    })
+    .collect();
+  commands.insert_resource(PickupVisuals(visuals));
+  commands.insert_resource(ShieldBubbleVisual {
+    mesh: meshes.add(
+      Path::circle(SHIELD_BUBBLE_RADIUS, 48)
+        .build_stroke_mesh(SHIELD_BUBBLE_WIDTH, LineJoin::Miter),
+    ),
+    material: materials.add(PickupKind::Shield.color().with_alpha(0.7)),
+  });
+}
+
+fn drop_pickups(
+  mut commands: Commands,
+  mut killed_events: EventReader<EnemyKilledEvent>,
+  visuals: Res<PickupVisuals>,
+) {
+  let mut rng = rand::thread_rng();
+  for EnemyKilledEvent { position, .. } in killed_events.read() {
+    if !rng.gen_bool(DROP_CHANCE) {
+      continue;
+    }
+
+    let kind = PickupKind::random(&mut rng);
+    let visual = &visuals.0[&kind];
+    commands
+      .spawn((
+        Pickup {
+          kind,
+          lifetime: Timer::from_seconds(PICKUP_LIFETIME_SECS, TimerMode::Once),
+        },
+        Transform::from_translation(position.extend(-0.5)),
+        Mesh2d(visual.fill.clone()),
+        MeshMaterial2d(visual.fill_material.clone()),
+        Visibility::Inherited,
+        Collider::Circle {
+          radius: PICKUP_RADIUS,
+        },
+        CollisionLayer::Pickup,
+        StateScoped(AppState::Playing),
+      ))
+      .with_child((
+        Mesh2d(visual.outline.clone()),
+        MeshMaterial2d(visual.outline_material.clone()),
+      ));
+  }
+}
+
+fn collect_pickups(
+  mut commands: Commands,
+  mut collision_events: EventReader<CollisionEvent>,
+  pickups: Query<&Pickup>,
+  mut player: Query<&mut Player>,
+  mut power_ups: ResMut<PowerUps>,
+  mut bomb_events: EventWriter<BombPickupEvent>,
+) {
+  for (player_entity, pickup_entity) in collision_events
+    .read()
+    .filter_map(|event| event.between(CollisionLayer::Player, CollisionLayer::Pickup))
+  {
+    let Ok(pickup) = pickups.get(pickup_entity) else {
+      continue;
+    };
+    commands.entity(pickup_entity).despawn_recursive();
+
+    match pickup.kind {
+      PickupKind::Health => {
+        let Ok(mut player) = player.get_mut(player_entity) else {
+          continue;
+        };
+        player.hp = (player.hp + HEALTH_RESTORED).min(player.max_hp);
+      }
+      PickupKind::DashBoost => {
+        power_ups.dash_boost = Duration::from_secs_f32(DASH_BOOST_SECS);
+      }
+      PickupKind::SwapHaste => {
+        power_ups.swap_haste = Duration::from_secs_f32(SWAP_HASTE_SECS);
+      }
+      PickupKind::Shield => {
+        commands.entity(player_entity).insert((
+          Invulnerable(Timer::from_seconds(SHIELD_SECS, TimerMode::Once)),
+          Shield(Timer::from_seconds(SHIELD_SECS, TimerMode::Once)),
+        ));
+      }
+      PickupKind::Bomb => {
+        bomb_events.send(BombPickupEvent {
+          pickup: pickup_entity,
+        });
+      }
+    }
+  }
+}
+
+// Deals enough to finish off every enemy on the screen. Ones still on their way in are spared.
+fn detonate_bomb_pickups(
+  mut bomb_events: EventReader<BombPickupEvent>,
+  enemies: Query<(Entity, &Enemy, &Transform)>,
+  play_area: Res<PlayArea>,
+  mut damage_events: EventWriter<DamageEvent>,
+) {
+  let screen = Rect::from_center_size(Vec2::ZERO, play_area.size_world);
+  for BombPickupEvent { pickup } in bomb_events.read() {
+    for (entity, enemy, transform) in enemies.iter() {
+      let position = transform.translation.xy();
+      if enemy.hp == 0 || !screen.contains(position) {
+        continue;
+      }
+      damage_events.send(DamageEvent {
+        target: entity,
+        amount: enemy.hp,
+        source: DamageSource::Bomb,
+        attack: Some(*pickup),
+        position,
+      });
+    }
+  }
+}
+
+fn expire_pickups(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut pickups: Query<(Entity, &mut Pickup)>,
+) {
+  for (entity, mut pickup) in pickups.iter_mut() {
+    if pickup.lifetime.tick(time.delta()).finished() {
+      commands.entity(entity).despawn_recursive();
+    }
+  }
+}
+
+fn blink_expiring_pickups(mut pickups: Query<(&Pickup, &mut Visibility)>) {
+  for (pickup, mut visibility) in pickups.iter_mut() {
+    let remaining = pickup.lifetime.remaining_secs();
+    let hidden =
+      remaining < PICKUP_BLINK_SECS && (remaining * PICKUP_BLINKS_PER_SEC) as u32 % 2 == 1;
+    visibility.set_if_neq(match hidden {
+      true => Visibility::Hidden,
+      false => Visibility::Inherited,
+    });
+  }
+}
+
+// Picking up another shield while one is up only tops up the time, so it keeps the same bubble.
+fn show_shield_bubbles(
+  mut commands: Commands,
+  shields: Query<Entity, Added<Shield>>,
+  visual: Res<ShieldBubbleVisual>,
+) {
+  for entity in shields.iter() {
+    commands.entity(entity).with_child((
+      ShieldBubble,
+      Transform::from_xyz(0., 0., 0.1),
+      Mesh2d(visual.mesh.clone()),
+      MeshMaterial2d(visual.material.clone()),
+    ));
+  }
+}
+
+fn tick_shields(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut shields: Query<(Entity, &mut Shield, &Children)>,
+  bubbles: Query<(), With<ShieldBubble>>,
+) {
+  for (entity, mut shield, children) in shields.iter_mut() {
+    if !shield.0.tick(time.delta()).finished() {
+      continue;
+    }
+    commands.entity(entity).remove::<Shield>();
+    for child in children.iter().filter(|child| bubbles.contains(**child)) {
+      commands.entity(*child).despawn_recursive();
+    }
+  }
+}
+
+fn tick_power_ups(mut power_ups: ResMut<PowerUps>, time: Res<Time>) {
+  power_ups.dash_boost = power_ups.dash_boost.saturating_sub(time.delta());
+  power_ups.swap_haste = power_ups.swap_haste.saturating_sub(time.delta());
+}
@@ -1,12 +1,22 @@
 use std::{f32::consts::PI, time::Duration};
 
-use bevy::prelude::*;
-use bevy_prototype_lyon::prelude::*;
+use bevy::{
+  asset::RenderAssetUsages,
+  diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+  prelude::*,
+  render::render_resource::PrimitiveTopology,
+};
 use rand::Rng;
 
 use crate::{
-  damage::ApplyDamageSet, window_setup::PlayArea, AppState, EnableStateScopedResource, Hand,
-  MouseControlConfig, MouseControlled, MOUSE_RADIUS, PLAYER_COLOR, RETICLE_COLOR,
+  collision::{Collider, CollisionLayer},
+  combo::Combo,
+  damage::{ApplyDamageSet, Armor, DamageSource, HitInvulnerability, Invulnerable},
+  health_display::{display_health, Health, HealthDisplay},
+  path::{LineJoin, Path},
+  window_setup::PlayArea,
+  AppState, EnableStateScopedResource, Hand, MouseControlConfig, MouseControlled, MOUSE_RADIUS,
+  PLAYER_COLOR, RETICLE_COLOR,
 };
 
 // MVP tasks:
@@ -28,7 +38,16 @@ impl Plugin for PlayingPlugin {
   fn build(&self, app: &mut App) {
     app
       .enable_state_scoped_resource::<EnemySpawnTimer>(AppState::Playing)
+      .enable_state_scoped_resource::<OffscreenEnemyCounts>(AppState::Playing)
       .enable_state_scoped_resource::<Score>(AppState::GameOver)
+      .add_event::<EnemyKilledEvent>()
+      .add_event::<SwapEvent>()
+      .register_type::<EnemyArchetypes>()
+      .init_resource::<EnemyArchetypes>()
+      .register_diagnostic(Diagnostic::new(ENEMIES_ALIVE))
+      .register_diagnostic(Diagnostic::new(ENEMIES_DESPAWNED_OFFSCREEN))
+      .register_diagnostic(Diagnostic::new(ENEMIES_WRAPPED))
+      .add_systems(Startup, build_enemy_meshes)
       .add_systems(
         OnEnter(AppState::Playing),
         (init_resources, spawn_or_respawn_player, spawn_score_display),
@@ -36,11 +55,14 @@ impl Plugin for PlayingPlugin {
       .add_systems(
         Update,
         (
-          move_enemies.in_set(MovesStuffSet),
-          (display_player_health, display_enemy_health).after(ApplyDamageSet),
+          (move_enemies, handle_offscreen_enemies)
+            .chain()
+            .in_set(MovesStuffSet),
+          (display_health::<Player>, display_health::<Enemy>).after(ApplyDamageSet),
           despawn_dead_enemies,
-          update_score_display,
+          (update_score_display, update_multiplier_display),
           spawn_enemy,
+          measure_enemies,
           game_over,
         )
           .chain()
@@ -50,7 +72,10 @@ impl Plugin for PlayingPlugin {
   }
 }
 
-const PLAYER_MAX_HP: u32 = 30;
+pub const PLAYER_MAX_HP: u32 = 30;
+pub const PLAYER_SPEED_LIMIT: f32 = 8.;
+// The player is big enough that a circle with fewer segments looks faceted.
+const PLAYER_CIRCLE_SEGMENTS: u32 = 48;
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MovesStuffSet;
@@ -58,95 +83,105 @@ pub struct MovesStuffSet;
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct Player {
   pub hp: u32,
+  pub max_hp: u32,
+}
+
+impl Health for Player {
+  fn hp(&self) -> u32 {
+    self.hp
+  }
+
+  fn max_hp(&self) -> u32 {
+    self.max_hp
+  }
 }
 
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct Reticle;
 
-#[derive(Component, Clone)]
-struct HealthDisplay {
-  shapes: Vec<Shape>,
+// Sent whenever the player and reticle trade places, with the player's old and new positions.
+#[derive(Event, Debug)]
+pub struct SwapEvent {
+  pub from: Vec2,
+  pub to: Vec2,
 }
 
 fn spawn_or_respawn_player(
   mut commands: Commands,
   mut cursors: Query<(Entity, &mut MouseControlled), (Without<Reticle>, Without<Player>)>,
-  mut player: Query<&mut Player>,
+  mut player: Query<(Entity, &mut Player)>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
   // Respawn case
-  for mut player in player.iter_mut() {
+  for (entity, mut player) in player.iter_mut() {
     player.hp = PLAYER_MAX_HP;
+    player.max_hp = PLAYER_MAX_HP;
+    commands.entity(entity).insert(spawn_protection());
   }
 
   // First spawn case
   for (entity, mut mouse_controlled) in cursors.iter_mut() {
     match mouse_controlled.hand {
       Some(Hand::Left) => {
-        let shape_for_hp = |hp: u32| -> Shape {
-          // Create a circle, filled from the bottom up in proportion to the player's health
-          // The top portion is hollow.
-          let normalized_hp = hp as f32 / PLAYER_MAX_HP as f32;
-          let arc_angle = (normalized_hp * 2. - 1.).asin();
-          let bottom_filled = ShapePath::new()
-            .move_to(Vec2::new(arc_angle.cos(), arc_angle.sin()) * MOUSE_RADIUS)
-            .arc(
-              Vec2::ZERO,
-              Vec2::splat(MOUSE_RADIUS),
-              (std::f32::consts::PI - 2. * arc_angle) - 2. * std::f32::consts::PI, // sweep angle
-              0.,
+        // A circle, filled from the bottom up in proportion to the player's health. The top portion
+        // is hollow.
+        let fills = (0..=PLAYER_MAX_HP)
+          .map(|hp| {
+            let fraction = hp as f32 / PLAYER_MAX_HP as f32;
+            meshes.add(
+              Path::filled_circle(MOUSE_RADIUS, fraction, PLAYER_CIRCLE_SEGMENTS)
+                .build_triangle_mesh()
+                .expect("circles are simple polygons"),
             )
-            .close();
-          ShapeBuilder::new()
-            .add(&bottom_filled)
-            .fill(PLAYER_COLOR)
-            .build()
-        };
+          })
+          .collect::<Vec<_>>();
+        let material = materials.add(PLAYER_COLOR);
         commands
           .entity(entity)
           .insert((
-            Player { hp: PLAYER_MAX_HP },
-            ShapeBuilder::new()
-              .add(&shapes::Circle {
-                radius: MOUSE_RADIUS,
-                center: Vec2::ZERO,
-              })
-              .stroke(Stroke {
-                color: PLAYER_COLOR,
-                options: StrokeOptions::default()
-                  .with_line_width(0.05)
-                  .with_tolerance(0.02),
-              })
-              .build(),
+            Player {
+              hp: PLAYER_MAX_HP,
+              max_hp: PLAYER_MAX_HP,
+            },
+            Armor(0),
+            HitInvulnerability(Duration::from_secs(1)),
+            spawn_protection(),
+            Collider::Circle {
+              radius: MOUSE_RADIUS,
+            },
+            CollisionLayer::Player,
+            Mesh2d(
+              meshes.add(
+                Path::circle(MOUSE_RADIUS, PLAYER_CIRCLE_SEGMENTS)
+                  .build_stroke_mesh(0.05, LineJoin::Miter),
+              ),
+            ),
+            MeshMaterial2d(material.clone()),
           ))
           .with_child((
-            HealthDisplay {
-              shapes: (0..=PLAYER_MAX_HP).map(shape_for_hp).collect(),
-            },
-            shape_for_hp(PLAYER_MAX_HP),
+            Mesh2d(fills[PLAYER_MAX_HP as usize].clone()),
+            MeshMaterial2d(material),
+            HealthDisplay { fills },
           ));
-        mouse_controlled.physics = MouseControlConfig::WithSpeedLimit(8.);
+        mouse_controlled.physics = MouseControlConfig::WithSpeedLimit(PLAYER_SPEED_LIMIT);
       }
       Some(Hand::Right) => {
-        let corner = |x: f32, y: f32| -> ShapePath {
-          ShapePath::new()
-            .move_to(Vec2::new(x * MOUSE_RADIUS, y * MOUSE_RADIUS / 2.))
-            .line_to(Vec2::new(x * MOUSE_RADIUS, y * MOUSE_RADIUS))
-            .line_to(Vec2::new(x * MOUSE_RADIUS / 2., y * MOUSE_RADIUS))
-        };
-        let shape = ShapeBuilder::new()
-          .add(&corner(1., 1.))
-          .add(&corner(-1., 1.))
-          .add(&corner(-1., -1.))
-          .add(&corner(1., -1.))
-          .add(
-            &ShapePath::new()
-              .move_to(Vec2::new(0., -0.05))
-              .line_to(Vec2::new(0., 0.05)),
-          )
-          .stroke(Stroke::new(RETICLE_COLOR, 0.1))
-          .build();
-
-        commands.entity(entity).insert((Reticle, shape));
+        // Four corner brackets around a short tick in the middle.
+        let mut path = Path::new();
+        for (x, y) in [(1., 1.), (-1., 1.), (-1., -1.), (1., -1.)] {
+          path.move_to(Vec2::new(x * MOUSE_RADIUS, y * MOUSE_RADIUS / 2.));
+          path.line_to(Vec2::new(x * MOUSE_RADIUS, y * MOUSE_RADIUS));
+          path.line_to(Vec2::new(x * MOUSE_RADIUS / 2., y * MOUSE_RADIUS));
+        }
+        path.move_to(Vec2::new(0., -0.05));
+        path.line_to(Vec2::new(0., 0.05));
+
+        commands.entity(entity).insert((
+          Reticle,
+          Mesh2d(meshes.add(path.build_stroke_mesh(0.1, LineJoin::Miter))),
+          MeshMaterial2d(materials.add(RETICLE_COLOR)),
+        ));
         mouse_controlled.physics = MouseControlConfig::Direct;
       }
       None => {}
@@ -154,18 +189,177 @@ fn spawn_or_respawn_player(
   }
 }
 
+// A moment of invulnerability so the player isn't hit before getting their bearings.
+fn spawn_protection() -> Invulnerable {
+  Invulnerable(Timer::from_seconds(1.0, TimerMode::Once))
+}
+
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct Enemy {
   pub hp: u32,
+  pub last_hit_by: Option<DamageSource>,
+  // Which attack that was, so kills from the same one can be told apart from separate ones.
+  pub last_attack: Option<Entity>,
   velocity: Vec2,
   radial_velocity: f32,
 }
 
+impl Health for Enemy {
+  fn hp(&self) -> u32 {
+    self.hp
+  }
+
+  fn max_hp(&self) -> u32 {
+    ENEMY_MAX_HP
+  }
+}
+
+impl Enemy {
+  // Reflects the enemy's velocity off a surface with the given normal, if it's heading into it.
+  pub fn bounce(&mut self, normal: Vec2) {
+    if self.velocity.dot(normal) < 0. {
+      self.velocity = self.velocity.reflect(normal);
+    }
+  }
+}
+
+// What happens to an enemy once it drifts out of the play area. Each enemy carries its own, copied
+// from its archetype when it spawns.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+enum OffscreenPolicy {
+  // Passes through once and is gone.
+  Despawn,
+  // Wraps around to the opposite edge until it's destroyed, asteroids style.
+  Wrap,
+}
+
+// The settings that set one kind of enemy apart from another.
+#[derive(Reflect, Debug, Clone, PartialEq)]
+struct EnemyArchetype {
+  offscreen_policy: OffscreenPolicy,
+}
+
+// Only drifters spawn so far. Editable in the world inspector, to try out other policies.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+struct EnemyArchetypes {
+  drifter: EnemyArchetype,
+}
+
+impl Default for EnemyArchetypes {
+  fn default() -> Self {
+    Self {
+      drifter: EnemyArchetype {
+        offscreen_policy: OffscreenPolicy::Despawn,
+      },
+    }
+  }
+}
+
+const ENEMY_COLOR: Color = Color::hsl(0., 0.95, 0.7);
+// How far past the edge of the play area enemies get before their offscreen policy kicks in.
+// Enemies spawn 0.5 outside, so this has to be bigger than that.
+const OFFSCREEN_MARGIN: f32 = 1.0;
+
+const ENEMIES_ALIVE: DiagnosticPath = DiagnosticPath::const_new("enemies/alive");
+const ENEMIES_DESPAWNED_OFFSCREEN: DiagnosticPath =
+  DiagnosticPath::const_new("enemies/despawned_offscreen");
+const ENEMIES_WRAPPED: DiagnosticPath = DiagnosticPath::const_new("enemies/wrapped");
+
+// Running totals for the current run, reported as diagnostics.
+#[derive(Resource, Debug, Default)]
+struct OffscreenEnemyCounts {
+  despawned: u32,
+  wrapped: u32,
+}
+
+#[derive(Event, Debug)]
+pub struct EnemyKilledEvent {
+  // None if the killing blow came from somewhere that doesn't record itself.
+  pub source: Option<DamageSource>,
+  // The damage area, projectile or pickup behind the killing blow, if there was one.
+  pub attack: Option<Entity>,
+  pub position: Vec2,
+  pub color: Color,
+}
+
+pub const ENEMY_RADIUS: f32 = 0.25;
+const ENEMY_MAX_HP: u32 = 10;
+const ENEMY_OUTLINE_WIDTH: f32 = 0.05;
+
+// The enemy triangle, pointing up and wound counterclockwise.
+fn enemy_outline() -> Path {
+  let angle = 2. / 3. * PI;
+  let mut path = Path::new();
+  path.move_to(Vec2::new(0., ENEMY_RADIUS));
+  path.line_to(Vec2::from_angle(angle + PI / 2.) * ENEMY_RADIUS);
+  path.line_to(Vec2::from_angle(2. * angle + PI / 2.) * ENEMY_RADIUS);
+  path.close();
+  path
+}
+
+// Every enemy looks the same, so they all share these instead of building their own on spawn.
 #[derive(Resource)]
-struct EnemySpawnTimer(Timer);
+struct EnemyMeshes {
+  outline: Handle<Mesh>,
+  // One per hp, from empty up to full.
+  fills: Vec<Handle<Mesh>>,
+  material: Handle<ColorMaterial>,
+}
+
+fn build_enemy_meshes(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+  commands.insert_resource(EnemyMeshes {
+    outline: meshes.add(enemy_outline().build_stroke_mesh(ENEMY_OUTLINE_WIDTH, LineJoin::Miter)),
+    fills: (0..=ENEMY_MAX_HP)
+      .map(|hp| meshes.add(enemy_fill_mesh(hp as f32 / ENEMY_MAX_HP as f32)))
+      .collect(),
+    material: materials.add(ENEMY_COLOR),
+  });
+}
+
+// The enemy triangle, filled from the bottom up in proportion to the enemy's health. So, the filled
+// part is a trapezoid.
+fn enemy_fill_mesh(fraction: f32) -> Mesh {
+  let outline = enemy_outline();
+  let [top, bottom_left, bottom_right] = outline.vertices[..] else {
+    unreachable!("the enemy outline is a triangle");
+  };
+  let top_left = bottom_left.lerp(top, fraction);
+  let top_right = bottom_right.lerp(top, fraction);
+  Mesh::new(
+    PrimitiveTopology::TriangleList,
+    RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+  )
+  .with_inserted_attribute(
+    Mesh::ATTRIBUTE_POSITION,
+    [
+      bottom_left,
+      bottom_right,
+      top_right,
+      bottom_left,
+      top_right,
+      top_left,
+    ]
+    .map(|v| v.extend(0.))
+    .to_vec(),
+  )
+}
+
+#[derive(Resource)]
+pub struct EnemySpawnTimer(Timer);
+
+impl EnemySpawnTimer {
+  pub fn enemies_per_sec(&self) -> f32 {
+    1. / self.0.duration().as_secs_f32()
+  }
+}
 
 #[derive(Resource)]
-struct Score(i32);
+pub struct Score(pub i32);
 
 fn init_resources(mut commands: Commands) {
   commands.insert_resource(EnemySpawnTimer(Timer::from_seconds(
@@ -173,6 +367,7 @@ fn init_resources(mut commands: Commands) {
     TimerMode::Repeating,
   )));
   commands.insert_resource(Score(0));
+  commands.insert_resource(OffscreenEnemyCounts::default());
 }
 
 fn spawn_enemy(
@@ -180,6 +375,8 @@ fn spawn_enemy(
   time: Res<Time>,
   mut timer: ResMut<EnemySpawnTimer>,
   play_area: Res<PlayArea>,
+  enemy_meshes: Res<EnemyMeshes>,
+  archetypes: Res<EnemyArchetypes>,
 ) {
   if !timer.0.tick(time.delta()).just_finished() {
     return;
@@ -208,52 +405,33 @@ fn spawn_enemy(
   let min_speed = 1.0;
   let max_speed = 4.0;
   let max_radial_velocity = 3.0;
-  let max_hp = 10;
-
-  let shape_for_hp = |hp: u32| -> Shape {
-    // The enemy is a triangle, filled from the bottom up in proportion to the enemy's health
-    // So, the bottom is a trapezoid.
-    let normalized_hp = hp as f32 / max_hp as f32;
-    let angle = 2. / 3. * std::f32::consts::PI;
-    let bottom_left = Vec2::from_angle(angle + PI / 2.).clamp_length_max(0.25);
-    let bottom_right = Vec2::from_angle(2. * angle + PI / 2.).clamp_length_max(0.25);
-    let top = Vec2::new(0., 1.).clamp_length_max(0.25);
-    let bottom_filled = ShapePath::new()
-      .move_to(bottom_left)
-      .line_to(bottom_left + (top - bottom_left) * normalized_hp)
-      .line_to(bottom_right + (top - bottom_right) * normalized_hp)
-      .line_to(bottom_right)
-      .close();
-    ShapeBuilder::new()
-      .add(&bottom_filled)
-      .fill(Color::hsl(0., 0.95, 0.7))
-      .build()
-  };
+  let archetype = &archetypes.drifter;
+  let material = &enemy_meshes.material;
 
   commands
     .spawn((
       Enemy {
-        hp: max_hp,
+        hp: ENEMY_MAX_HP,
+        last_hit_by: None,
+        last_attack: None,
         velocity: -spawn_direction * rng.gen_range(min_speed..max_speed),
         radial_velocity: rng.gen_range(-max_radial_velocity..max_radial_velocity),
       },
+      archetype.offscreen_policy,
       Transform::from_translation(spawn_position.extend(0.0)),
       GlobalTransform::default(),
-      ShapeBuilder::new()
-        .add(&shapes::RegularPolygon {
-          sides: 3,
-          center: Vec2::ZERO,
-          feature: RegularPolygonFeature::Radius(0.25),
-        })
-        .stroke(Stroke::new(Color::hsl(0., 0.95, 0.7), 0.05))
-        .build(),
+      Mesh2d(enemy_meshes.outline.clone()),
+      MeshMaterial2d(material.clone()),
+      Collider::convex_from_path(&enemy_outline()),
+      CollisionLayer::Enemy,
       StateScoped(AppState::Playing),
     ))
     .with_child((
+      Mesh2d(enemy_meshes.fills[ENEMY_MAX_HP as usize].clone()),
+      MeshMaterial2d(material.clone()),
       HealthDisplay {
-        shapes: (0..=max_hp).map(shape_for_hp).collect(),
+        fills: enemy_meshes.fills.clone(),
       },
-      shape_for_hp(max_hp),
     ));
 
   // Spawn rate should go up linearly with time (enemies per second per second is constant)
@@ -275,50 +453,58 @@ fn move_enemies(mut enemies: Query<(&mut Transform, &Enemy)>, time: Res<Time>) {
   }
 }
 
-fn despawn_dead_enemies(
+fn handle_offscreen_enemies(
   mut commands: Commands,
-  enemies: Query<(Entity, &Enemy)>,
-  mut score: ResMut<Score>,
+  mut enemies: Query<(Entity, &mut Transform, &OffscreenPolicy)>,
+  play_area: Res<PlayArea>,
+  mut counts: ResMut<OffscreenEnemyCounts>,
 ) {
-  for (entity, enemy) in enemies.iter() {
-    if enemy.hp == 0 {
-      commands.entity(entity).despawn_recursive();
-      score.0 += 1;
+  let bounds = play_area.size_world / 2. + Vec2::splat(OFFSCREEN_MARGIN);
+  for (entity, mut transform, policy) in enemies.iter_mut() {
+    let position = transform.translation.xy();
+    if position.abs().cmple(bounds).all() {
+      continue;
+    }
+
+    match policy {
+      OffscreenPolicy::Despawn => {
+        commands.entity(entity).despawn_recursive();
+        counts.despawned += 1;
+      }
+      OffscreenPolicy::Wrap => {
+        // Reappear just past the opposite edge, still heading the same way.
+        let wrapped = (position + bounds).rem_euclid(2. * bounds) - bounds;
+        transform.translation = wrapped.extend(transform.translation.z);
+        counts.wrapped += 1;
+      }
     }
   }
 }
 
-fn display_player_health(
-  mut commands: Commands,
-  player: Query<(&Player, &Children)>,
-  health_displays: Query<(Entity, &HealthDisplay)>,
+fn measure_enemies(
+  mut diagnostics: Diagnostics,
+  enemies: Query<(), With<Enemy>>,
+  counts: Res<OffscreenEnemyCounts>,
 ) {
-  let (player, children) = player.single();
-  for child in children.iter() {
-    let Ok((entity, health_display)) = health_displays.get(*child) else {
-      continue;
-    };
-
-    commands
-      .entity(entity)
-      .insert(health_display.shapes[player.hp as usize].clone());
-  }
+  diagnostics.add_measurement(&ENEMIES_ALIVE, || enemies.iter().len() as f64);
+  diagnostics.add_measurement(&ENEMIES_DESPAWNED_OFFSCREEN, || counts.despawned as f64);
+  diagnostics.add_measurement(&ENEMIES_WRAPPED, || counts.wrapped as f64);
 }
 
-fn display_enemy_health(
+fn despawn_dead_enemies(
   mut commands: Commands,
-  enemies: Query<(&Enemy, &Children)>,
-  health_displays: Query<(Entity, &HealthDisplay)>,
+  enemies: Query<(Entity, &Enemy, &Transform)>,
+  mut killed_events: EventWriter<EnemyKilledEvent>,
 ) {
-  for (enemy, children) in enemies.iter() {
-    for child in children.iter() {
-      let Ok((entity, health_display)) = health_displays.get(*child) else {
-        continue;
-      };
-
-      commands
-        .entity(entity)
-        .insert(health_display.shapes[enemy.hp as usize].clone());
+  for (entity, enemy, transform) in enemies.iter() {
+    if enemy.hp == 0 {
+      commands.entity(entity).despawn_recursive();
+      killed_events.send(EnemyKilledEvent {
+        source: enemy.last_hit_by,
+        attack: enemy.last_attack,
+        position: transform.translation.xy(),
+        color: ENEMY_COLOR,
+      });
     }
   }
 }
@@ -326,6 +512,9 @@ fn display_enemy_health(
 #[derive(Component)]
 struct ScoreDisplay;
 
+#[derive(Component)]
+struct MultiplierDisplay;
+
 fn spawn_score_display(mut commands: Commands) {
   commands
     .spawn((
@@ -349,6 +538,15 @@ fn spawn_score_display(mut commands: Commands) {
         font_size: 30.0,
         ..default()
       },
+    ))
+    .with_child((
+      TextSpan::default(),
+      MultiplierDisplay,
+      TextFont {
+        font_size: 24.0,
+        ..default()
+      },
+      TextColor(RETICLE_COLOR),
     ));
 }
 
@@ -358,6 +556,15 @@ fn update_score_display(score: Res<Score>, mut text: Query<&mut TextSpan, With<S
   }
 }
 
+fn update_multiplier_display(
+  combo: Res<Combo>,
+  mut text: Query<&mut TextSpan, With<MultiplierDisplay>>,
+) {
+  for mut text in text.iter_mut() {
+    text.0 = format!("  x{}", combo.multiplier());
+  }
+}
+
 fn game_over(player: Query<&Player>, mut next_state: ResMut<NextState<AppState>>) {
   let player = player.single();
 
@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+// Where a file lives under one of the platform's per-user directories, if it has one.
+pub fn path_in(dir: Option<PathBuf>, file_name: &str) -> Option<PathBuf> {
+  dir.map(|dir| dir.join("twin-mouse-shooter").join(file_name))
+}
+
+// `what` names the contents in warnings, like "settings".
+pub fn load<T: DeserializeOwned + Default>(path: Option<&Path>, what: &str) -> T {
+  let Some(path) = path else {
+    return T::default();
+  };
+  let Ok(contents) = std::fs::read_to_string(path) else {
+    // Most likely this is the first run and nothing has been saved yet.
+    return T::default();
+  };
+  ron::from_str(&contents).unwrap_or_else(|err| {
+    warn!("Ignoring unreadable {} at {:?}: {}", what, path, err);
+    T::default()
+  })
+}
+
+pub fn save<T: Serialize>(value: &T, path: Option<&Path>, what: &str) {
+  let Some(path) = path else {
+    warn!("No directory available, {} will not be saved", what);
+    return;
+  };
+  let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+    .map_err(|err| err.to_string())
+    .and_then(|contents| {
+      if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+      }
+      std::fs::write(path, contents).map_err(|err| err.to_string())
+    });
+  if let Err(err) = result {
+    warn!("Failed to save {} to {:?}: {}", what, path, err);
+  }
+}
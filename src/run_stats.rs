@@ -0,0 +1,120 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+  damage::{DamageAreaResolvedEvent, DamageSource, ResolvedDamageEvent},
+  playing::{EnemyKilledEvent, EnemySpawnTimer, Player, SwapEvent},
+  AppState, EnableStateScopedResource,
+};
+
+pub struct RunStatsPlugin;
+
+impl Plugin for RunStatsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .enable_state_scoped_resource::<RunStats>(AppState::GameOver)
+      .add_systems(OnEnter(AppState::Playing), init_resources)
+      .add_systems(
+        // PostUpdate so that events sent on the frame the player dies still get counted.
+        PostUpdate,
+        (
+          tick_time_survived,
+          record_kills,
+          record_damage,
+          record_swaps,
+          record_attacks,
+          record_peak_spawn_rate,
+        )
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+// Bookkeeping for the current run, kept around through the game over screen.
+#[derive(Resource, Debug, Default)]
+pub struct RunStats {
+  pub time_survived: Duration,
+  // Kills nothing claimed are kept under None, so they still count toward the total.
+  pub kills_by_source: HashMap<Option<DamageSource>, u32>,
+  pub damage_taken: u32,
+  pub damage_dealt: u32,
+  pub crits: u32,
+  pub swaps: u32,
+  pub peak_enemies_per_sec: f32,
+  // Damage areas (dashes, bombs, shots) that went off, and how many of them hit anything.
+  pub attacks: u32,
+  pub attacks_landed: u32,
+}
+
+impl RunStats {
+  pub fn kills(&self) -> u32 {
+    self.kills_by_source.values().sum()
+  }
+
+  pub fn kills_from(&self, source: DamageSource) -> u32 {
+    self
+      .kills_by_source
+      .get(&Some(source))
+      .copied()
+      .unwrap_or(0)
+  }
+
+  // Fraction of attacks that hit at least one enemy, if any attacks were made.
+  pub fn hit_rate(&self) -> Option<f32> {
+    (self.attacks > 0).then(|| self.attacks_landed as f32 / self.attacks as f32)
+  }
+}
+
+fn init_resources(mut commands: Commands) {
+  commands.insert_resource(RunStats::default());
+}
+
+fn tick_time_survived(mut stats: ResMut<RunStats>, time: Res<Time>) {
+  stats.time_survived += time.delta();
+}
+
+fn record_kills(mut stats: ResMut<RunStats>, mut killed_events: EventReader<EnemyKilledEvent>) {
+  for EnemyKilledEvent { source, .. } in killed_events.read() {
+    *stats.kills_by_source.entry(*source).or_default() += 1;
+  }
+}
+
+fn record_damage(
+  mut stats: ResMut<RunStats>,
+  mut damage_events: EventReader<ResolvedDamageEvent>,
+  player: Query<(), With<Player>>,
+) {
+  for event in damage_events.read() {
+    if player.contains(event.target) {
+      stats.damage_taken += event.amount;
+    } else {
+      stats.damage_dealt += event.amount;
+    }
+    if event.crit {
+      stats.crits += 1;
+    }
+  }
+}
+
+fn record_swaps(mut stats: ResMut<RunStats>, mut swap_events: EventReader<SwapEvent>) {
+  stats.swaps += swap_events.read().count() as u32;
+}
+
+fn record_attacks(
+  mut stats: ResMut<RunStats>,
+  mut resolved_events: EventReader<DamageAreaResolvedEvent>,
+) {
+  for DamageAreaResolvedEvent { hits } in resolved_events.read() {
+    stats.attacks += 1;
+    if *hits > 0 {
+      stats.attacks_landed += 1;
+    }
+  }
+}
+
+fn record_peak_spawn_rate(mut stats: ResMut<RunStats>, spawn_timer: Res<EnemySpawnTimer>) {
+  stats.peak_enemies_per_sec = stats
+    .peak_enemies_per_sec
+    .max(spawn_timer.enemies_per_sec());
+}
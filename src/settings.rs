@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+use bevy::{input::common_conditions::input_toggle_active, prelude::*};
+use bevy_inspector_egui::quick::ResourceInspectorPlugin;
+use serde::{Deserialize, Serialize};
+
+use crate::ron_file;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .register_type::<Settings>()
+      .insert_resource(Settings::load())
+      // Last, so it hears about exits sent from anywhere earlier in the frame.
+      .add_systems(Last, save_settings)
+      // A developer view, alongside the world inspector, which also frees the OS cursor to click on
+      // it. Players change settings from the settings menu.
+      .add_plugins(
+        ResourceInspectorPlugin::<Settings>::new()
+          .run_if(input_toggle_active(false, KeyCode::Backquote)),
+      );
+  }
+}
+
+const SAVE_DELAY_SECS: f32 = 0.5;
+
+// Player preferences, persisted to the platform config directory.
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[reflect(Resource)]
+// Settings missing from an older file keep their defaults.
+#[serde(default)]
+pub struct Settings {
+  pub screen_shake: EffectSetting,
+  pub hit_stop: EffectSetting,
+  pub zoom_punch: EffectSetting,
+  pub volume: Volumes,
+  pub display_mode: DisplayMode,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayMode {
+  #[default]
+  Windowed,
+  // Covers the screen with a borderless window at the desktop resolution.
+  Borderless,
+  // Takes over the screen exclusively.
+  Fullscreen,
+}
+
+impl DisplayMode {
+  pub const ALL: [DisplayMode; 3] = [
+    DisplayMode::Windowed,
+    DisplayMode::Borderless,
+    DisplayMode::Fullscreen,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      DisplayMode::Windowed => "Windowed",
+      DisplayMode::Borderless => "Borderless",
+      DisplayMode::Fullscreen => "Fullscreen",
+    }
+  }
+}
+
+// A visual effect that can be toned down, or switched off entirely for anyone it bothers.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct EffectSetting {
+  pub enabled: bool,
+  // From 0 to 1.
+  pub intensity: f32,
+}
+
+impl Default for EffectSetting {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      intensity: 1.0,
+    }
+  }
+}
+
+impl EffectSetting {
+  // How strongly to apply the effect, with 0 meaning not at all.
+  pub fn strength(&self) -> f32 {
+    match self.enabled {
+      true => self.intensity.clamp(0., 1.),
+      false => 0.,
+    }
+  }
+}
+
+// Each from 0 to 1. Sound effects and music are both scaled by the master volume.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Volumes {
+  pub master: f32,
+  pub sfx: f32,
+  pub music: f32,
+}
+
+impl Default for Volumes {
+  fn default() -> Self {
+    Self {
+      master: 1.0,
+      sfx: 1.0,
+      music: 0.6,
+    }
+  }
+}
+
+impl Volumes {
+  pub fn sfx(&self) -> f32 {
+    (self.master * self.sfx).clamp(0., 1.)
+  }
+
+  pub fn music(&self) -> f32 {
+    (self.master * self.music).clamp(0., 1.)
+  }
+}
+
+impl Settings {
+  fn path() -> Option<PathBuf> {
+    ron_file::path_in(dirs::config_dir(), "settings.ron")
+  }
+
+  fn load() -> Self {
+    ron_file::load(Self::path().as_deref(), "settings")
+  }
+
+  fn save(&self) {
+    ron_file::save(self, Self::path().as_deref(), "settings");
+  }
+}
+
+// Waits for the settings to stop changing before writing them, rather than writing on every frame
+// a value is dragged in the inspector. Anything still waiting is written on exit.
+fn save_settings(
+  settings: Res<Settings>,
+  time: Res<Time<Real>>,
+  mut exit_events: EventReader<AppExit>,
+  mut pending: Local<Option<Timer>>,
+) {
+  // Nothing to save until something is actually changed.
+  if settings.is_changed() && !settings.is_added() {
+    *pending = Some(Timer::from_seconds(SAVE_DELAY_SECS, TimerMode::Once));
+  }
+  let Some(timer) = pending.as_mut() else {
+    return;
+  };
+  let exiting = exit_events.read().next().is_some();
+  if timer.tick(time.delta()).finished() || exiting {
+    settings.save();
+    *pending = None;
+  }
+}
@@ -0,0 +1,317 @@
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{
+  settings::{DisplayMode, EffectSetting, Settings, Volumes},
+  AppState, EnableStateScopedResource, RETICLE_COLOR,
+};
+
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_state::<SettingsMenu>()
+      .enable_state_scoped_entities::<SettingsMenu>()
+      .enable_state_scoped_resource::<MenuCursor>(SettingsMenu::Open)
+      .add_systems(OnEnter(AppState::Intro), spawn_hint)
+      .add_systems(OnEnter(AppState::GameOver), spawn_hint)
+      .add_systems(OnEnter(SettingsMenu::Open), spawn_menu)
+      .add_systems(
+        Update,
+        (
+          // Only between runs, so opening it never has to pause the game.
+          toggle_menu
+            .run_if(input_just_pressed(KeyCode::Tab))
+            .run_if(in_state(AppState::Intro).or(in_state(AppState::GameOver))),
+          (
+            navigate_menu,
+            update_menu.run_if(resource_changed::<Settings>.or(resource_changed::<MenuCursor>)),
+          )
+            .chain()
+            .run_if(in_state(SettingsMenu::Open)),
+        )
+          .chain(),
+      );
+  }
+}
+
+const FRACTION_STEP: f32 = 0.1;
+
+// Whether the player is looking at the settings menu. Screens that take mouse input leave it alone
+// while the menu is open.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SettingsMenu {
+  #[default]
+  Closed,
+  Open,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Effect {
+  ScreenShake,
+  HitStop,
+  ZoomPunch,
+}
+
+impl Effect {
+  fn name(&self) -> &'static str {
+    match self {
+      Effect::ScreenShake => "Screen shake",
+      Effect::HitStop => "Hit stop",
+      Effect::ZoomPunch => "Zoom punch",
+    }
+  }
+
+  fn setting<'a>(&self, settings: &'a Settings) -> &'a EffectSetting {
+    match self {
+      Effect::ScreenShake => &settings.screen_shake,
+      Effect::HitStop => &settings.hit_stop,
+      Effect::ZoomPunch => &settings.zoom_punch,
+    }
+  }
+
+  fn setting_mut<'a>(&self, settings: &'a mut Settings) -> &'a mut EffectSetting {
+    match self {
+      Effect::ScreenShake => &mut settings.screen_shake,
+      Effect::HitStop => &mut settings.hit_stop,
+      Effect::ZoomPunch => &mut settings.zoom_punch,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Channel {
+  Master,
+  Sfx,
+  Music,
+}
+
+impl Channel {
+  fn name(&self) -> &'static str {
+    match self {
+      Channel::Master => "Master volume",
+      Channel::Sfx => "Sound effects volume",
+      Channel::Music => "Music volume",
+    }
+  }
+
+  fn volume(&self, volumes: &Volumes) -> f32 {
+    match self {
+      Channel::Master => volumes.master,
+      Channel::Sfx => volumes.sfx,
+      Channel::Music => volumes.music,
+    }
+  }
+
+  fn volume_mut<'a>(&self, volumes: &'a mut Volumes) -> &'a mut f32 {
+    match self {
+      Channel::Master => &mut volumes.master,
+      Channel::Sfx => &mut volumes.sfx,
+      Channel::Music => &mut volumes.music,
+    }
+  }
+}
+
+// One line of the menu, and the setting it changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+  Enabled(Effect),
+  Intensity(Effect),
+  Volume(Channel),
+  DisplayMode,
+}
+
+const ROWS: [Row; 10] = [
+  Row::Enabled(Effect::ScreenShake),
+  Row::Intensity(Effect::ScreenShake),
+  Row::Enabled(Effect::HitStop),
+  Row::Intensity(Effect::HitStop),
+  Row::Enabled(Effect::ZoomPunch),
+  Row::Intensity(Effect::ZoomPunch),
+  Row::Volume(Channel::Master),
+  Row::Volume(Channel::Sfx),
+  Row::Volume(Channel::Music),
+  Row::DisplayMode,
+];
+
+impl Row {
+  fn label(&self) -> String {
+    match self {
+      Row::Enabled(effect) => effect.name().to_string(),
+      Row::Intensity(effect) => format!("{} intensity", effect.name()),
+      Row::Volume(channel) => channel.name().to_string(),
+      Row::DisplayMode => "Display".to_string(),
+    }
+  }
+
+  fn value(&self, settings: &Settings) -> String {
+    match self {
+      Row::Enabled(effect) => match effect.setting(settings).enabled {
+        true => "On".to_string(),
+        false => "Off".to_string(),
+      },
+      Row::Intensity(effect) => percent(effect.setting(settings).intensity),
+      Row::Volume(channel) => percent(channel.volume(&settings.volume)),
+      Row::DisplayMode => settings.display_mode.name().to_string(),
+    }
+  }
+
+  // Steps the setting up or down. On/off settings flip whichever way they're stepped, and the
+  // display mode cycles round.
+  fn adjust(&self, settings: &mut Settings, steps: i32) {
+    match self {
+      Row::Enabled(effect) => {
+        let setting = effect.setting_mut(settings);
+        setting.enabled = !setting.enabled;
+      }
+      Row::Intensity(effect) => {
+        let setting = effect.setting_mut(settings);
+        setting.intensity = step_fraction(setting.intensity, steps);
+      }
+      Row::Volume(channel) => {
+        let volume = channel.volume_mut(&mut settings.volume);
+        *volume = step_fraction(*volume, steps);
+      }
+      Row::DisplayMode => {
+        let modes = DisplayMode::ALL;
+        let i = modes
+          .iter()
+          .position(|mode| *mode == settings.display_mode)
+          .unwrap_or(0);
+        settings.display_mode = modes[(i as i32 + steps).rem_euclid(modes.len() as i32) as usize];
+      }
+    }
+  }
+}
+
+// Moves a 0 to 1 value by whole steps, snapping to the nearest one so repeated steps don't drift.
+fn step_fraction(value: f32, steps: i32) -> f32 {
+  (((value / FRACTION_STEP).round() + steps as f32) * FRACTION_STEP).clamp(0., 1.)
+}
+
+fn percent(fraction: f32) -> String {
+  format!("{:.0}%", fraction * 100.)
+}
+
+// The highlighted row.
+#[derive(Resource, Debug, Default)]
+struct MenuCursor(usize);
+
+#[derive(Component)]
+struct MenuRow(usize);
+
+fn spawn_hint(mut commands: Commands, state: Res<State<AppState>>) {
+  commands
+    .spawn((
+      Node {
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(15.0),
+        width: Val::Percent(100.0),
+        justify_content: JustifyContent::Center,
+        ..default()
+      },
+      StateScoped(state.get().clone()),
+    ))
+    .with_child((
+      Text::new("Tab: settings"),
+      TextFont {
+        font_size: 18.0,
+        ..default()
+      },
+    ));
+}
+
+fn toggle_menu(state: Res<State<SettingsMenu>>, mut next_state: ResMut<NextState<SettingsMenu>>) {
+  next_state.set(match state.get() {
+    SettingsMenu::Closed => SettingsMenu::Open,
+    SettingsMenu::Open => SettingsMenu::Closed,
+  });
+}
+
+fn spawn_menu(mut commands: Commands) {
+  commands.insert_resource(MenuCursor::default());
+  commands
+    .spawn((
+      Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        row_gap: Val::Px(12.0),
+        ..default()
+      },
+      BackgroundColor(Color::BLACK.with_alpha(0.85)),
+      // Over whichever screen it was opened from.
+      GlobalZIndex(1),
+      StateScoped(SettingsMenu::Open),
+    ))
+    .with_children(|screen| {
+      screen.spawn((
+        Text::new("Settings"),
+        TextFont {
+          font_size: 40.0,
+          ..default()
+        },
+      ));
+      for i in 0..ROWS.len() {
+        screen.spawn((
+          Text::default(),
+          TextFont {
+            font_size: 22.0,
+            ..default()
+          },
+          MenuRow(i),
+        ));
+      }
+      screen.spawn((
+        Text::new("Up/down to choose, left/right to change, Tab to close"),
+        TextFont {
+          font_size: 18.0,
+          ..default()
+        },
+      ));
+    });
+}
+
+fn navigate_menu(
+  keys: Res<ButtonInput<KeyCode>>,
+  mut cursor: ResMut<MenuCursor>,
+  mut settings: ResMut<Settings>,
+) {
+  if keys.just_pressed(KeyCode::ArrowUp) {
+    cursor.0 = (cursor.0 + ROWS.len() - 1) % ROWS.len();
+  }
+  if keys.just_pressed(KeyCode::ArrowDown) {
+    cursor.0 = (cursor.0 + 1) % ROWS.len();
+  }
+
+  let steps = [
+    (KeyCode::ArrowRight, 1),
+    (KeyCode::Enter, 1),
+    (KeyCode::ArrowLeft, -1),
+  ]
+  .into_iter()
+  .filter(|(key, _)| keys.just_pressed(*key))
+  .map(|(_, step)| step)
+  .sum::<i32>();
+  // Only touched when something changes, so opening the menu doesn't save the settings.
+  if steps != 0 {
+    ROWS[cursor.0].adjust(&mut settings, steps);
+  }
+}
+
+fn update_menu(
+  settings: Res<Settings>,
+  cursor: Res<MenuCursor>,
+  mut rows: Query<(&MenuRow, &mut Text, &mut TextColor)>,
+) {
+  for (MenuRow(i), mut text, mut color) in rows.iter_mut() {
+    let row = ROWS[*i];
+    text.0 = format!("{}: {}", row.label(), row.value(&settings));
+    color.0 = match *i == cursor.0 {
+      true => RETICLE_COLOR,
+      false => Color::WHITE,
+    };
+  }
+}
@@ -0,0 +1,232 @@
+use std::f32::consts::TAU;
+
+use rand::Rng;
+
+// Sound effect synthesis in the style of sfxr: one oscillator with a pitch sweep, shaped by an
+// attack/sustain/decay envelope.
+
+const SAMPLE_RATE: u32 = 44100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+  Square { duty: f32 },
+  Saw,
+  Sine,
+  // A new random level every oscillator cycle, so the frequency still sets the pitch of the hiss.
+  Noise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SfxParams {
+  pub waveform: Waveform,
+  // Frequency at the start and end of the sound, in Hz. The sweep between them is exponential, so
+  // it sounds even to the ear.
+  pub start_hz: f32,
+  pub end_hz: f32,
+  pub attack_secs: f32,
+  pub sustain_secs: f32,
+  pub decay_secs: f32,
+  pub volume: f32,
+}
+
+impl SfxParams {
+  // The same sound, nudged a little in pitch and length so repeats don't sound identical.
+  pub fn vary(&self, rng: &mut impl Rng) -> Self {
+    let pitch = rng.gen_range(0.92..=1.08);
+    let length = rng.gen_range(0.9..=1.1);
+    Self {
+      start_hz: self.start_hz * pitch,
+      end_hz: self.end_hz * pitch,
+      attack_secs: self.attack_secs * length,
+      sustain_secs: self.sustain_secs * length,
+      decay_secs: self.decay_secs * length,
+      ..*self
+    }
+  }
+
+  fn duration_secs(&self) -> f32 {
+    self.attack_secs + self.sustain_secs + self.decay_secs
+  }
+
+  fn envelope(&self, t: f32) -> f32 {
+    if t < self.attack_secs {
+      t / self.attack_secs
+    } else if t < self.attack_secs + self.sustain_secs {
+      1.
+    } else {
+      let decayed = t - self.attack_secs - self.sustain_secs;
+      (1. - decayed / self.decay_secs).max(0.)
+    }
+  }
+}
+
+// Renders the sound to samples between -1 and 1.
+fn synthesize(params: &SfxParams, rng: &mut impl Rng) -> Vec<f32> {
+  let duration = params.duration_secs();
+  let sample_count = (duration * SAMPLE_RATE as f32) as usize;
+  let mut phase = 0.;
+  let mut noise = 0.;
+  (0..sample_count)
+    .map(|i| {
+      let t = i as f32 / SAMPLE_RATE as f32;
+      let hz = params.start_hz * (params.end_hz / params.start_hz).powf(t / duration);
+      let previous_phase = phase;
+      phase = (phase + hz / SAMPLE_RATE as f32).fract();
+
+      let wave = match params.waveform {
+        Waveform::Square { duty } => match phase < duty {
+          true => 1.,
+          false => -1.,
+        },
+        Waveform::Saw => 2. * phase - 1.,
+        Waveform::Sine => (phase * TAU).sin(),
+        Waveform::Noise => {
+          // Wrapped around, so a new cycle started.
+          if phase < previous_phase {
+            noise = rng.gen_range(-1.0..=1.0);
+          }
+          noise
+        }
+      };
+      wave * params.envelope(t) * params.volume
+    })
+    .collect()
+}
+
+// A complete mono, 16 bit PCM WAV file, ready to hand to the audio loader.
+pub fn synthesize_wav(params: &SfxParams, rng: &mut impl Rng) -> Vec<u8> {
+  encode_wav(&synthesize(params, rng))
+}
+
+// One sound in a loop, starting some way into it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+  pub start_secs: f32,
+  pub params: SfxParams,
+}
+
+// Mixes the notes into a WAV file exactly `loop_secs` long. Anything still sounding at the end
+// wraps around to the start, so the loop repeats without a seam.
+pub fn synthesize_loop_wav(notes: &[Note], loop_secs: f32, rng: &mut impl Rng) -> Vec<u8> {
+  let mut mix = vec![0.; (loop_secs * SAMPLE_RATE as f32) as usize];
+  let loop_len = mix.len();
+  for note in notes {
+    let start = (note.start_secs * SAMPLE_RATE as f32) as usize;
+    for (i, sample) in synthesize(&note.params, rng).into_iter().enumerate() {
+      mix[(start + i) % loop_len] += sample;
+    }
+  }
+  encode_wav(&mix)
+}
+
+fn encode_wav(samples: &[f32]) -> Vec<u8> {
+  let data_len = samples.len() as u32 * 2;
+
+  let mut wav = Vec::with_capacity(44 + data_len as usize);
+  wav.extend_from_slice(b"RIFF");
+  wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+  wav.extend_from_slice(b"WAVE");
+
+  wav.extend_from_slice(b"fmt ");
+  wav.extend_from_slice(&16u32.to_le_bytes());
+  // PCM, one channel.
+  wav.extend_from_slice(&1u16.to_le_bytes());
+  wav.extend_from_slice(&1u16.to_le_bytes());
+  wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+  // Bytes per second, then bytes per sample, then bits per sample.
+  wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+  wav.extend_from_slice(&2u16.to_le_bytes());
+  wav.extend_from_slice(&16u16.to_le_bytes());
+
+  wav.extend_from_slice(b"data");
+  wav.extend_from_slice(&data_len.to_le_bytes());
+  for sample in samples {
+    let quantized = (sample.clamp(-1., 1.) * i16::MAX as f32) as i16;
+    wav.extend_from_slice(&quantized.to_le_bytes());
+  }
+  wav
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::{rngs::StdRng, SeedableRng};
+
+  use super::*;
+
+  // A steady square wave, so every sample while it's sustained is well away from zero.
+  fn tone(attack_secs: f32, sustain_secs: f32, decay_secs: f32) -> SfxParams {
+    SfxParams {
+      waveform: Waveform::Square { duty: 0.5 },
+      start_hz: 440.,
+      end_hz: 440.,
+      attack_secs,
+      sustain_secs,
+      decay_secs,
+      volume: 0.5,
+    }
+  }
+
+  fn u32_at(wav: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(wav[offset..offset + 4].try_into().unwrap())
+  }
+
+  fn samples(wav: &[u8]) -> Vec<i16> {
+    wav[44..]
+      .chunks_exact(2)
+      .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+      .collect()
+  }
+
+  #[test]
+  fn header_lengths_match_the_samples() {
+    let samples = [0., 0.5, -0.5, 1., -1.];
+    let wav = encode_wav(&samples);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..12], b"WAVE");
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(wav.len(), 44 + 2 * samples.len());
+    // The RIFF chunk covers everything after its own header.
+    assert_eq!(u32_at(&wav, 4) as usize, wav.len() - 8);
+    assert_eq!(u32_at(&wav, 40) as usize, 2 * samples.len());
+    assert_eq!(u32_at(&wav, 24), SAMPLE_RATE);
+  }
+
+  #[test]
+  fn samples_are_quantized_and_clamped() {
+    let wav = encode_wav(&[0., 1., -1., 2., -2.]);
+    assert_eq!(samples(&wav), [0, i16::MAX, -i16::MAX, i16::MAX, -i16::MAX]);
+  }
+
+  #[test]
+  fn sounds_last_as_long_as_their_envelope() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let wav = synthesize_wav(&tone(0.1, 0.2, 0.2), &mut rng);
+    assert_eq!(samples(&wav).len(), SAMPLE_RATE as usize / 2);
+    assert_eq!(u32_at(&wav, 40), SAMPLE_RATE);
+  }
+
+  #[test]
+  fn loops_are_exactly_loop_secs_long() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let wav = synthesize_loop_wav(&[], 2.0, &mut rng);
+    assert_eq!(samples(&wav).len(), 2 * SAMPLE_RATE as usize);
+    assert!(samples(&wav).iter().all(|sample| *sample == 0));
+  }
+
+  #[test]
+  fn notes_past_the_end_of_a_loop_wrap_into_its_start() {
+    let mut rng = StdRng::seed_from_u64(0);
+    // Sustained from 0.91s to 1.09s of a one second loop.
+    let note = Note {
+      start_secs: 0.9,
+      params: tone(0.01, 0.18, 0.01),
+    };
+    let samples = samples(&synthesize_loop_wav(&[note], 1.0, &mut rng));
+    assert_eq!(samples.len(), SAMPLE_RATE as usize);
+
+    let at = |secs: f32| samples[(secs * SAMPLE_RATE as f32) as usize];
+    assert_ne!(at(0.05), 0, "the tail wraps around to the start");
+    assert_eq!(at(0.5), 0, "silent between the tail and the note");
+    assert_ne!(at(0.95), 0, "the note itself");
+  }
+}
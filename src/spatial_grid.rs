@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+// Uniform grid of square cells, for finding things near a point or area without checking
+// everything. Each item is stored in every cell its bounding box touches.
+pub struct SpatialGrid<T> {
+  cell_size: f32,
+  cells: HashMap<IVec2, Vec<(T, Rect)>>,
+}
+
+impl<T: Copy> SpatialGrid<T> {
+  pub fn new(cell_size: f32) -> Self {
+    Self {
+      cell_size,
+      cells: HashMap::new(),
+    }
+  }
+
+  // Empties the grid, keeping allocations around for the cells that were in use.
+  pub fn clear(&mut self) {
+    self.cells.retain(|_, items| {
+      let in_use = !items.is_empty();
+      items.clear();
+      in_use
+    });
+  }
+
+  // Boxes that are inside out or not finite, like the bounds of a shape with no points, can't be
+  // placed in any cell and are left out.
+  pub fn insert(&mut self, item: T, aabb: Rect) {
+    if !is_valid(aabb) {
+      return;
+    }
+    let min_cell = self.cell_of(aabb.min);
+    let max_cell = self.cell_of(aabb.max);
+    for y in min_cell.y..=max_cell.y {
+      for x in min_cell.x..=max_cell.x {
+        self
+          .cells
+          .entry(IVec2::new(x, y))
+          .or_default()
+          .push((item, aabb));
+      }
+    }
+  }
+
+  // Items whose bounding boxes overlap the rect. Each item is returned once.
+  pub fn query_rect(&self, rect: Rect) -> impl Iterator<Item = T> + '_ {
+    self.entries_overlapping(rect).map(|(item, _)| item)
+  }
+
+  fn entries_overlapping(&self, rect: Rect) -> impl Iterator<Item = (T, Rect)> + '_ {
+    // An invalid rect covers no cells at all.
+    let (min_cell, max_cell) = match is_valid(rect) {
+      true => (self.cell_of(rect.min), self.cell_of(rect.max)),
+      false => (IVec2::ONE, IVec2::ZERO),
+    };
+    (min_cell.y..=max_cell.y)
+      .flat_map(move |y| (min_cell.x..=max_cell.x).map(move |x| IVec2::new(x, y)))
+      .filter_map(|cell| Some((cell, self.cells.get(&cell)?)))
+      .flat_map(move |(cell, items)| {
+        items.iter().filter_map(move |(item, aabb)| {
+          let overlaps = aabb.min.x <= rect.max.x
+            && aabb.max.x >= rect.min.x
+            && aabb.min.y <= rect.max.y
+            && aabb.max.y >= rect.min.y;
+          // An item spanning several cells is only reported from the first cell of the overlap.
+          let first_shared_cell = self.cell_of(aabb.min.max(rect.min));
+          (overlaps && first_shared_cell == cell).then_some((*item, *aabb))
+        })
+      })
+  }
+
+  fn cell_of(&self, point: Vec2) -> IVec2 {
+    (point / self.cell_size).floor().as_ivec2()
+  }
+}
+
+fn is_valid(rect: Rect) -> bool {
+  rect.min.is_finite() && rect.max.is_finite() && rect.min.cmple(rect.max).all()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use rand::{rngs::StdRng, Rng, SeedableRng};
+
+  use super::*;
+
+  fn overlaps(a: Rect, b: Rect) -> bool {
+    a.min.x <= b.max.x && a.max.x >= b.min.x && a.min.y <= b.max.y && a.max.y >= b.min.y
+  }
+
+  fn grid_of(boxes: &[Rect]) -> SpatialGrid<usize> {
+    let mut grid = SpatialGrid::new(1.0);
+    for (i, aabb) in boxes.iter().enumerate() {
+      grid.insert(i, *aabb);
+    }
+    grid
+  }
+
+  #[test]
+  fn candidates_match_brute_force() {
+    for seed in 0..50 {
+      let mut rng = StdRng::seed_from_u64(seed);
+      // Mostly enemy sized, with the odd one spanning many cells, either side of the origin.
+      let boxes = (0..200)
+        .map(|_| {
+          let center = Vec2::new(rng.gen_range(-8.0..8.0), rng.gen_range(-4.5..4.5));
+          let half_size = match rng.gen_bool(0.05) {
+            true => Vec2::new(rng.gen_range(1.0..6.0), rng.gen_range(1.0..4.0)),
+            false => Vec2::splat(rng.gen_range(0.05..0.5)),
+          };
+          Rect::from_center_half_size(center, half_size)
+        })
+        .collect::<Vec<_>>();
+      let grid = grid_of(&boxes);
+
+      for (i, aabb) in boxes.iter().enumerate() {
+        let found = grid.query_rect(*aabb).collect::<Vec<_>>();
+        let unique = found.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(found.len(), unique.len(), "box {} got duplicates", i);
+        let expected = (0..boxes.len())
+          .filter(|j| overlaps(*aabb, boxes[*j]))
+          .collect::<HashSet<_>>();
+        assert_eq!(unique, expected, "box {}", i);
+      }
+    }
+  }
+
+  #[test]
+  fn boxes_touching_across_a_cell_boundary_are_found() {
+    let left = Rect::new(0.5, 0.2, 1.0, 0.8);
+    let right = Rect::new(1.0, 0.2, 1.5, 0.8);
+    let grid = grid_of(&[left, right]);
+    assert_eq!(
+      grid.query_rect(left).collect::<HashSet<_>>(),
+      HashSet::from([0, 1])
+    );
+    assert_eq!(
+      grid.query_rect(right).collect::<HashSet<_>>(),
+      HashSet::from([0, 1])
+    );
+  }
+
+  #[test]
+  fn large_boxes_are_reported_once() {
+    let wall = Rect::new(-10.0, -0.1, 10.0, 0.1);
+    let grid = grid_of(&[wall]);
+    assert_eq!(
+      grid.query_rect(Rect::new(-20.0, -1.0, 20.0, 1.0)).count(),
+      1
+    );
+    assert_eq!(grid.query_rect(Rect::new(3.2, -0.5, 3.4, 0.5)).count(), 1);
+    assert_eq!(grid.query_rect(Rect::new(3.2, 0.5, 3.4, 0.9)).count(), 0);
+  }
+
+  #[test]
+  fn invalid_boxes_are_left_out() {
+    let inside_out = Rect {
+      min: Vec2::INFINITY,
+      max: Vec2::NEG_INFINITY,
+    };
+    let not_a_number = Rect {
+      min: Vec2::NAN,
+      max: Vec2::NAN,
+    };
+    let grid = grid_of(&[inside_out, not_a_number, Rect::new(0.0, 0.0, 1.0, 1.0)]);
+    assert!(grid.cells.values().all(|entries| entries.len() == 1));
+    assert_eq!(
+      grid
+        .query_rect(Rect::new(-100.0, -100.0, 100.0, 100.0))
+        .count(),
+      1
+    );
+    assert_eq!(grid.query_rect(inside_out).count(), 0);
+    assert_eq!(grid.query_rect(not_a_number).count(), 0);
+  }
+
+  #[test]
+  fn clear_empties_the_grid() {
+    let mut grid = grid_of(&[Rect::new(0.0, 0.0, 1.0, 1.0)]);
+    grid.clear();
+    assert_eq!(grid.query_rect(Rect::new(-5.0, -5.0, 5.0, 5.0)).count(), 0);
+  }
+}
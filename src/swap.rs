@@ -0,0 +1,185 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+  arena::ArenaBlockers,
+  collision::Collider,
+  mischief::{poll_events, MischiefEvent, MischiefEventData},
+  pickups::PowerUps,
+  playing::{MovesStuffSet, Player, Reticle, SwapEvent},
+  AppState, EnableStateScopedResource, MouseControlled, PlayPhase, MOUSE_RADIUS, RETICLE_COLOR,
+};
+
+pub struct SwapPlugin;
+
+impl Plugin for SwapPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .enable_state_scoped_resource::<SwapCharges>(AppState::Playing)
+      .enable_state_scoped_resource::<Aiming>(AppState::Playing)
+      .add_systems(OnEnter(AppState::Playing), init_resources)
+      .add_systems(
+        Update,
+        (
+          recharge_swaps,
+          // Not part of MovesStuffSet, which is paused during drafts: it has to keep reading clicks
+          // so the ones that pick an upgrade don't pile up and swap afterwards.
+          swap_on_release.after(poll_events).before(MovesStuffSet),
+          draw_swap_meter.after(MovesStuffSet),
+        )
+          .chain()
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+const MAX_SWAP_CHARGES: u32 = 2;
+const SWAP_RECHARGE_SECS: f32 = 1.0;
+const METER_RADIUS: f32 = MOUSE_RADIUS + 0.15;
+// Space between the meter's segments, in radians.
+const METER_GAP: f32 = 0.3;
+
+#[derive(Resource, Debug)]
+pub struct SwapCharges {
+  charges: u32,
+  // Runs while any charges are missing, restoring one each time it finishes.
+  recharge: Timer,
+}
+
+impl SwapCharges {
+  // 1 when a swap is available, otherwise how close the next charge is.
+  pub fn readiness(&self) -> f32 {
+    match self.charges {
+      0 => self.recharge.fraction(),
+      _ => 1.,
+    }
+  }
+}
+
+// Whether a mouse button is held down, with a swap ready to go when it's released.
+#[derive(Resource, Debug, Default)]
+pub struct Aiming(pub bool);
+
+fn init_resources(mut commands: Commands) {
+  commands.insert_resource(SwapCharges {
+    charges: MAX_SWAP_CHARGES,
+    recharge: Timer::from_seconds(SWAP_RECHARGE_SECS, TimerMode::Repeating),
+  });
+  commands.insert_resource(Aiming::default());
+}
+
+fn recharge_swaps(mut swaps: ResMut<SwapCharges>, power_ups: Res<PowerUps>, time: Res<Time>) {
+  if swaps.charges == MAX_SWAP_CHARGES {
+    return;
+  }
+
+  let delta = time.delta().mul_f32(power_ups.swap_speed());
+  if swaps.recharge.tick(delta).just_finished() {
+    swaps.charges += 1;
+    if swaps.charges == MAX_SWAP_CHARGES {
+      swaps.recharge.reset();
+    }
+  }
+}
+
+// Holding either mouse button aims, and letting go swaps the player and reticle if a charge is
+// available. Clicks during a draft are dropped. The player stops at any wall or obstacle in the way,
+// while the reticle goes wherever the player was.
+fn swap_on_release(
+  phase: Res<State<PlayPhase>>,
+  blockers: Res<ArenaBlockers>,
+  (mut swaps, mut aiming): (ResMut<SwapCharges>, ResMut<Aiming>),
+  mut mouse_events: EventReader<MischiefEvent>,
+  mut player: Query<
+    (&mut Transform, &mut MouseControlled, &Collider),
+    (With<Player>, Without<Reticle>),
+  >,
+  mut reticle: Query<(&mut Transform, &mut MouseControlled), (With<Reticle>, Without<Player>)>,
+  mut swap_events: EventWriter<SwapEvent>,
+) {
+  if *phase.get() == PlayPhase::Drafting {
+    mouse_events.clear();
+    aiming.0 = false;
+    return;
+  }
+
+  for MischiefEvent {
+    device: _,
+    event_data,
+  } in mouse_events.read()
+  {
+    let MischiefEventData::Button { button: _, pressed } = event_data else {
+      continue;
+    };
+    if *pressed {
+      aiming.0 = true;
+      continue;
+    }
+    if !aiming.0 {
+      continue;
+    }
+    aiming.0 = false;
+
+    if swaps.charges == 0 {
+      continue;
+    }
+    swaps.charges -= 1;
+
+    let (mut player_transform, mut player_control, collider) = player.single_mut();
+    let (mut reticle_transform, mut reticle_control) = reticle.single_mut();
+
+    let from = player_transform.translation;
+    let to = blockers.sweep(collider, from.xy(), reticle_transform.translation.xy());
+    player_transform.translation = to.extend(from.z);
+    reticle_transform.translation = from.with_z(reticle_transform.translation.z);
+    std::mem::swap(
+      &mut player_transform.rotation,
+      &mut reticle_transform.rotation,
+    );
+
+    std::mem::swap(&mut player_control.id, &mut reticle_control.id);
+    std::mem::swap(&mut player_control.hand, &mut reticle_control.hand);
+
+    swap_events.send(SwapEvent {
+      from: from.xy(),
+      to,
+    });
+  }
+}
+
+// One segment per charge around the reticle, clockwise from the top. Spent charges are dim, and
+// the next one fills in as it recharges.
+fn draw_swap_meter(
+  mut gizmos: Gizmos,
+  swaps: Res<SwapCharges>,
+  aiming: Res<Aiming>,
+  reticle: Query<&Transform, With<Reticle>>,
+) {
+  let Ok(reticle) = reticle.get_single() else {
+    return;
+  };
+  let center = reticle.translation.xy();
+  let color = match aiming.0 {
+    true => Color::WHITE,
+    false => RETICLE_COLOR,
+  };
+
+  let segment_angle = TAU / MAX_SWAP_CHARGES as f32;
+  let mut draw_segment = |index: u32, fraction: f32, color: Color| {
+    let length = (segment_angle - METER_GAP) * fraction;
+    let start = segment_angle * index as f32 + METER_GAP / 2.;
+    // Arcs run counterclockwise from the rotation, so rotate to the clockwise end.
+    let isometry = Isometry2d::new(center, Rot2::radians(-(start + length)));
+    gizmos.arc_2d(isometry, length, METER_RADIUS, color);
+  };
+
+  for index in 0..MAX_SWAP_CHARGES {
+    draw_segment(index, 1., color.with_alpha(0.2));
+    if index < swaps.charges {
+      draw_segment(index, 1., color);
+    } else if index == swaps.charges {
+      draw_segment(index, swaps.recharge.fraction(), color);
+    }
+  }
+}
@@ -0,0 +1,415 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::{
+  bomb_surprise::{BOMB_DELAY_SECS, BOMB_HALF_SIZE},
+  collision::DetectCollisionsSet,
+  damage::ApplyDamageSet,
+  dash_swap::{DASH_DAMAGE, DASH_HALF_HEIGHT},
+  mischief::{MischiefEvent, MischiefEventData},
+  playing::{MovesStuffSet, Player, PLAYER_MAX_HP, PLAYER_SPEED_LIMIT},
+  AppState, EnableStateScopedResource, GameMode, MouseControlConfig, MouseControlled, PlayPhase,
+  RETICLE_COLOR,
+};
+
+pub struct UpgradesPlugin;
+
+impl Plugin for UpgradesPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_sub_state::<PlayPhase>()
+      .enable_state_scoped_entities::<PlayPhase>()
+      .enable_state_scoped_resource::<DraftTimer>(AppState::Playing)
+      .enable_state_scoped_resource::<Upgrades>(AppState::GameOver)
+      .enable_state_scoped_resource::<Draft>(PlayPhase::Drafting)
+      // Nothing moves or gets hurt while the player is choosing.
+      .configure_sets(
+        Update,
+        (MovesStuffSet, DetectCollisionsSet, ApplyDamageSet)
+          .run_if(not(in_state(PlayPhase::Drafting))),
+      )
+      .add_systems(
+        OnEnter(AppState::Playing),
+        (init_resources, spawn_upgrades_display),
+      )
+      .add_systems(OnEnter(PlayPhase::Drafting), (pause, spawn_draft))
+      .add_systems(OnExit(PlayPhase::Drafting), unpause)
+      .add_systems(
+        Update,
+        (
+          start_draft.run_if(in_state(PlayPhase::Running)),
+          (choose_upgrade, highlight_chosen_card)
+            .chain()
+            .run_if(in_state(PlayPhase::Drafting)),
+          apply_speed_limit.run_if(resource_exists_and_changed::<Upgrades>),
+          update_upgrades_display.run_if(resource_exists_and_changed::<Upgrades>),
+        )
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+const DRAFT_INTERVAL_SECS: f32 = 30.0;
+const OFFERS_PER_DRAFT: usize = 3;
+// Sideways mouse motion needed to move the highlight over by one card.
+const COUNTS_PER_CARD: i32 = 80;
+// Ignore clicks for a moment, so one meant for the game doesn't pick a card by accident.
+const DRAFT_INPUT_DELAY_SECS: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Upgrade {
+  Speed,
+  DashDamage,
+  DashWidth,
+  BombSize,
+  BombFuse,
+  MaxHp,
+  FireRate,
+  ShotDamage,
+}
+
+impl Upgrade {
+  const ALL: [Upgrade; 8] = [
+    Upgrade::Speed,
+    Upgrade::DashDamage,
+    Upgrade::DashWidth,
+    Upgrade::BombSize,
+    Upgrade::BombFuse,
+    Upgrade::MaxHp,
+    Upgrade::FireRate,
+    Upgrade::ShotDamage,
+  ];
+
+  fn name(&self) -> &'static str {
+    match self {
+      Upgrade::Speed => "Fleet Foot",
+      Upgrade::DashDamage => "Sharp Dash",
+      Upgrade::DashWidth => "Wide Dash",
+      Upgrade::BombSize => "Big Bombs",
+      Upgrade::BombFuse => "Short Fuse",
+      Upgrade::MaxHp => "Thick Skin",
+      Upgrade::FireRate => "Hair Trigger",
+      Upgrade::ShotDamage => "Hot Rounds",
+    }
+  }
+
+  fn description(&self) -> &'static str {
+    match self {
+      Upgrade::Speed => "Move faster",
+      Upgrade::DashDamage => "Dashes deal more damage",
+      Upgrade::DashWidth => "Dashes hit a wider path",
+      Upgrade::BombSize => "Bombs cover more area",
+      Upgrade::BombFuse => "Bombs go off sooner",
+      Upgrade::MaxHp => "More max hp, and heal that much",
+      Upgrade::FireRate => "Every weapon fires faster",
+      Upgrade::ShotDamage => "Shots deal more damage",
+    }
+  }
+
+  // Only upgrades for the swap mechanic in play are offered. The weapons fire in every mode.
+  fn available_in(&self, mode: GameMode) -> bool {
+    match self {
+      Upgrade::Speed | Upgrade::MaxHp | Upgrade::FireRate | Upgrade::ShotDamage => true,
+      Upgrade::DashDamage | Upgrade::DashWidth => mode == GameMode::DashSwap,
+      Upgrade::BombSize | Upgrade::BombFuse => mode == GameMode::BombSurprise,
+    }
+  }
+}
+
+// Upgrades taken this run, in the order they were taken, and the stats they add up to.
+#[derive(Resource, Debug, Default)]
+pub struct Upgrades {
+  taken: Vec<Upgrade>,
+}
+
+impl Upgrades {
+  fn stacks(&self, upgrade: Upgrade) -> u32 {
+    self.taken.iter().filter(|taken| **taken == upgrade).count() as u32
+  }
+
+  pub fn speed_limit(&self) -> f32 {
+    PLAYER_SPEED_LIMIT + 1.5 * self.stacks(Upgrade::Speed) as f32
+  }
+
+  pub fn dash_damage(&self) -> u32 {
+    DASH_DAMAGE + 5 * self.stacks(Upgrade::DashDamage)
+  }
+
+  pub fn dash_half_height(&self) -> f32 {
+    DASH_HALF_HEIGHT + 0.15 * self.stacks(Upgrade::DashWidth) as f32
+  }
+
+  pub fn bomb_half_size(&self) -> f32 {
+    BOMB_HALF_SIZE + 0.3 * self.stacks(Upgrade::BombSize) as f32
+  }
+
+  pub fn bomb_delay_secs(&self) -> f32 {
+    BOMB_DELAY_SECS * 0.75f32.powi(self.stacks(Upgrade::BombFuse) as i32)
+  }
+
+  fn max_hp(&self) -> u32 {
+    PLAYER_MAX_HP + 10 * self.stacks(Upgrade::MaxHp)
+  }
+
+  // Weapons differ in their base stats, so these scale whichever one is in hand.
+  pub fn fire_interval_secs(&self, base: f32) -> f32 {
+    base * 0.85f32.powi(self.stacks(Upgrade::FireRate) as i32)
+  }
+
+  pub fn shot_damage(&self, base: u32) -> u32 {
+    base + self.stacks(Upgrade::ShotDamage)
+  }
+}
+
+#[derive(Resource)]
+struct DraftTimer(Timer);
+
+// The offers on the table, and which one either mouse is pointing at.
+#[derive(Resource, Debug)]
+struct Draft {
+  offers: Vec<Upgrade>,
+  chosen: usize,
+  motion: i32,
+  input_delay: Timer,
+}
+
+fn init_resources(mut commands: Commands) {
+  commands.insert_resource(DraftTimer(Timer::from_seconds(
+    DRAFT_INTERVAL_SECS,
+    TimerMode::Repeating,
+  )));
+  commands.insert_resource(Upgrades::default());
+}
+
+fn start_draft(
+  mut timer: ResMut<DraftTimer>,
+  time: Res<Time>,
+  mut next_phase: ResMut<NextState<PlayPhase>>,
+) {
+  if timer.0.tick(time.delta()).just_finished() {
+    next_phase.set(PlayPhase::Drafting);
+  }
+}
+
+fn pause(mut time: ResMut<Time<Virtual>>) {
+  time.pause();
+}
+
+fn unpause(mut time: ResMut<Time<Virtual>>) {
+  time.unpause();
+}
+
+#[derive(Component)]
+struct UpgradeCard(usize);
+
+fn spawn_draft(mut commands: Commands, mode: Res<GameMode>) {
+  let mut offers = Upgrade::ALL
+    .into_iter()
+    .filter(|upgrade| upgrade.available_in(*mode))
+    .collect::<Vec<_>>();
+  offers.shuffle(&mut rand::thread_rng());
+  offers.truncate(OFFERS_PER_DRAFT);
+
+  commands
+    .spawn((
+      Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        row_gap: Val::Px(30.0),
+        ..default()
+      },
+      StateScoped(PlayPhase::Drafting),
+    ))
+    .with_children(|screen| {
+      screen.spawn((
+        Text::new("Choose an upgrade"),
+        TextFont {
+          font_size: 40.0,
+          ..default()
+        },
+      ));
+      screen
+        .spawn(Node {
+          column_gap: Val::Px(30.0),
+          ..default()
+        })
+        .with_children(|cards| {
+          for (i, upgrade) in offers.iter().enumerate() {
+            cards
+              .spawn((
+                UpgradeCard(i),
+                Node {
+                  width: Val::Px(220.0),
+                  flex_direction: FlexDirection::Column,
+                  row_gap: Val::Px(10.0),
+                  padding: UiRect::all(Val::Px(20.0)),
+                  border: UiRect::all(Val::Px(3.0)),
+                  ..default()
+                },
+                BorderColor(Color::WHITE),
+                BackgroundColor(Color::BLACK.with_alpha(0.6)),
+              ))
+              .with_child((
+                Text::new(upgrade.name()),
+                TextFont {
+                  font_size: 26.0,
+                  ..default()
+                },
+              ))
+              .with_child((
+                Text::new(upgrade.description()),
+                TextFont {
+                  font_size: 18.0,
+                  ..default()
+                },
+              ));
+          }
+        });
+      screen.spawn((
+        Text::new("Move either mouse sideways to choose, click to take it"),
+        TextFont {
+          font_size: 20.0,
+          ..default()
+        },
+      ));
+    });
+
+  commands.insert_resource(Draft {
+    offers,
+    chosen: 0,
+    motion: 0,
+    input_delay: Timer::from_seconds(DRAFT_INPUT_DELAY_SECS, TimerMode::Once),
+  });
+}
+
+fn choose_upgrade(
+  mut draft: ResMut<Draft>,
+  mut upgrades: ResMut<Upgrades>,
+  mut player: Query<&mut Player>,
+  mut mouse_events: EventReader<MischiefEvent>,
+  mut next_phase: ResMut<NextState<PlayPhase>>,
+  time: Res<Time<Real>>,
+) {
+  // Game time is paused, so wait out the input delay in real time.
+  if !draft.input_delay.tick(time.delta()).finished() {
+    mouse_events.clear();
+    return;
+  }
+
+  for MischiefEvent {
+    device: _,
+    event_data,
+  } in mouse_events.read()
+  {
+    match event_data {
+      MischiefEventData::RelMotion { x, y: _ } => {
+        draft.motion += x;
+        let steps = draft.motion / COUNTS_PER_CARD;
+        draft.motion -= steps * COUNTS_PER_CARD;
+        let last = draft.offers.len() as i32 - 1;
+        draft.chosen = (draft.chosen as i32 + steps).clamp(0, last) as usize;
+      }
+      MischiefEventData::Button {
+        button: _,
+        pressed: true,
+      } => {
+        let upgrade = draft.offers[draft.chosen];
+        upgrades.taken.push(upgrade);
+        if upgrade == Upgrade::MaxHp {
+          let mut player = player.single_mut();
+          let gained = upgrades.max_hp() - player.max_hp;
+          player.max_hp += gained;
+          player.hp += gained;
+        }
+        next_phase.set(PlayPhase::Running);
+        return;
+      }
+      _ => {}
+    }
+  }
+}
+
+fn highlight_chosen_card(draft: Res<Draft>, mut cards: Query<(&UpgradeCard, &mut BorderColor)>) {
+  for (card, mut border) in cards.iter_mut() {
+    border.0 = match card.0 == draft.chosen {
+      true => RETICLE_COLOR,
+      false => Color::WHITE,
+    };
+  }
+}
+
+// The speed limit belongs to whichever entity is the player, so it's reapplied when it changes.
+fn apply_speed_limit(
+  upgrades: Res<Upgrades>,
+  mut player: Query<&mut MouseControlled, With<Player>>,
+) {
+  for mut mouse_controlled in player.iter_mut() {
+    mouse_controlled.physics = MouseControlConfig::WithSpeedLimit(upgrades.speed_limit());
+  }
+}
+
+#[derive(Component)]
+struct UpgradesDisplay;
+
+fn spawn_upgrades_display(mut commands: Commands) {
+  commands.spawn((
+    Text::default(),
+    TextFont {
+      font_size: 18.0,
+      ..default()
+    },
+    Node {
+      position_type: PositionType::Absolute,
+      bottom: Val::Px(15.0),
+      left: Val::Px(15.0),
+      ..default()
+    },
+    UpgradesDisplay,
+    StateScoped(AppState::GameOver),
+  ));
+}
+
+fn update_upgrades_display(
+  upgrades: Res<Upgrades>,
+  mut text: Query<&mut Text, With<UpgradesDisplay>>,
+) {
+  // Grouped by upgrade, in the order each was first taken.
+  let mut lines: Vec<String> = Vec::new();
+  for (i, upgrade) in upgrades.taken.iter().enumerate() {
+    if upgrades.taken[..i].contains(upgrade) {
+      continue;
+    }
+    lines.push(match upgrades.stacks(*upgrade) {
+      1 => upgrade.name().to_string(),
+      stacks => format!("{} x{}", upgrade.name(), stacks),
+    });
+  }
+
+  for mut text in text.iter_mut() {
+    text.0 = lines.join("\n");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_mode_has_a_full_draft() {
+    for mode in [GameMode::DashSwap, GameMode::BombSurprise, GameMode::Shoot] {
+      let available = Upgrade::ALL
+        .iter()
+        .filter(|upgrade| upgrade.available_in(mode))
+        .count();
+      assert!(
+        available >= OFFERS_PER_DRAFT,
+        "{:?} offers {}",
+        mode,
+        available
+      );
+    }
+  }
+}
@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+  arena::ArenaBlockers,
+  collision::{Collider, CollisionEvent, CollisionLayer, DetectCollisionsSet, SpatialIndex},
+  damage::{DamageArea, DamageAreaResolvedEvent, DamageEvent, DamageSource, DetectDamageSet},
+  mischief::{MischiefEvent, MischiefEventData},
+  path::Path,
+  playing::{Enemy, MovesStuffSet, Player, Reticle},
+  upgrades::Upgrades,
+  window_setup::PlayArea,
+  AppState, EnableStateScopedResource, PlayPhase,
+};
+
+pub struct WeaponsPlugin;
+
+impl Plugin for WeaponsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .enable_state_scoped_resource::<Weapon>(AppState::Playing)
+      .enable_state_scoped_resource::<FireCooldown>(AppState::Playing)
+      .add_systems(Startup, build_projectile_visuals)
+      .add_systems(
+        OnEnter(AppState::Playing),
+        (init_resources, spawn_weapon_display),
+      )
+      .add_systems(
+        Update,
+        (
+          switch_weapon.run_if(not(in_state(PlayPhase::Drafting))),
+          reset_fire_cooldown.run_if(resource_exists_and_changed::<Upgrades>),
+          (move_projectiles, expire_projectiles)
+            .chain()
+            .in_set(MovesStuffSet),
+          fire.after(MovesStuffSet).before(DetectCollisionsSet),
+          (projectile_hits, stop_projectiles_at_obstacles)
+            .chain()
+            .in_set(DetectDamageSet),
+          update_weapon_display.run_if(resource_exists_and_changed::<Weapon>),
+        )
+          .chain()
+          .run_if(in_state(AppState::Playing)),
+      );
+  }
+}
+
+const PROJECTILE_RADIUS: f32 = 0.1;
+const PROJECTILE_LIFETIME_SECS: f32 = 2.0;
+// How quickly homing shots turn toward their target, in radians per second.
+const HOMING_TURN_RATE: f32 = 6.0;
+// How far away homing shots notice enemies.
+const HOMING_RANGE: f32 = 8.0;
+const BEAM_HALF_WIDTH: f32 = 0.15;
+const PROJECTILE_SEGMENTS: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum WeaponKind {
+  Rapid,
+  Spread,
+  Beam,
+  Homing,
+}
+
+struct WeaponStats {
+  fire_interval_secs: f32,
+  damage: u32,
+  // Projectiles per shot, fanned out evenly across the spread angle.
+  projectiles: u32,
+  spread: f32,
+  speed: f32,
+  // Enemies each projectile can hit before it's used up.
+  pierce: u32,
+  color: Color,
+}
+
+impl WeaponKind {
+  const ALL: [WeaponKind; 4] = [
+    WeaponKind::Rapid,
+    WeaponKind::Spread,
+    WeaponKind::Beam,
+    WeaponKind::Homing,
+  ];
+
+  fn name(&self) -> &'static str {
+    match self {
+      WeaponKind::Rapid => "Rapid Fire",
+      WeaponKind::Spread => "Spread",
+      WeaponKind::Beam => "Piercing Beam",
+      WeaponKind::Homing => "Homing",
+    }
+  }
+
+  fn stats(&self) -> WeaponStats {
+    match self {
+      WeaponKind::Rapid => WeaponStats {
+        fire_interval_secs: 0.08,
+        damage: 1,
+        projectiles: 1,
+        spread: 0.,
+        speed: 20.,
+        pierce: 1,
+        color: Color::hsl(60., 0.95, 0.7),
+      },
+      WeaponKind::Spread => WeaponStats {
+        fire_interval_secs: 0.35,
+        damage: 2,
+        projectiles: 5,
+        spread: 0.6,
+        speed: 14.,
+        pierce: 1,
+        color: Color::hsl(30., 0.95, 0.7),
+      },
+      // Hits everything between the player and the reticle instantly, so speed doesn't apply. Stops
+      // at the first wall or obstacle.
+      WeaponKind::Beam => WeaponStats {
+        fire_interval_secs: 0.5,
+        damage: 4,
+        projectiles: 1,
+        spread: 0.,
+        speed: 0.,
+        pierce: u32::MAX,
+        color: Color::hsl(180., 0.95, 0.8),
+      },
+      WeaponKind::Homing => WeaponStats {
+        fire_interval_secs: 0.25,
+        damage: 2,
+        projectiles: 1,
+        spread: 0.,
+        speed: 10.,
+        pierce: 1,
+        color: Color::hsl(120., 0.95, 0.7),
+      },
+    }
+  }
+
+  fn next(&self) -> WeaponKind {
+    let index = WeaponKind::ALL
+      .iter()
+      .position(|kind| kind == self)
+      .unwrap_or(0);
+    WeaponKind::ALL[(index + 1) % WeaponKind::ALL.len()]
+  }
+}
+
+#[derive(Resource, Debug)]
+struct Weapon(WeaponKind);
+
+// Time until the weapon in hand can fire again.
+#[derive(Resource, Debug)]
+struct FireCooldown(Timer);
+
+impl FireCooldown {
+  fn for_weapon(kind: WeaponKind, upgrades: &Upgrades) -> Self {
+    FireCooldown(Timer::from_seconds(
+      upgrades.fire_interval_secs(kind.stats().fire_interval_secs),
+      TimerMode::Repeating,
+    ))
+  }
+}
+
+#[derive(Component, Debug)]
+struct Projectile {
+  velocity: Vec2,
+  damage: u32,
+  pierce: u32,
+  homing: bool,
+  // Enemies already hit, so a piercing shot doesn't hit the same one every frame it overlaps.
+  hit: Vec<Entity>,
+  lifetime: Timer,
+}
+
+// Every projectile is the same circle, colored for the weapon that fired it.
+#[derive(Resource)]
+struct ProjectileVisuals {
+  mesh: Handle<Mesh>,
+  materials: HashMap<WeaponKind, Handle<ColorMaterial>>,
+}
+
+// The run's upgrades aren't in yet, so the cooldown is reset once they are.
+fn init_resources(mut commands: Commands) {
+  commands.insert_resource(Weapon(WeaponKind::Rapid));
+  commands.insert_resource(FireCooldown::for_weapon(
+    WeaponKind::Rapid,
+    &Upgrades::default(),
+  ));
+}
+
+// Scrolling either mouse's wheel cycles through the weapons.
+fn switch_weapon(
+  mut weapon: ResMut<Weapon>,
+  mut cooldown: ResMut<FireCooldown>,
+  mut mouse_events: EventReader<MischiefEvent>,
+  upgrades: Res<Upgrades>,
+) {
+  for MischiefEvent {
+    device: _,
+    event_data,
+  } in mouse_events.read()
+  {
+    if matches!(event_data, MischiefEventData::Scroll) {
+      weapon.0 = weapon.0.next();
+      *cooldown = FireCooldown::for_weapon(weapon.0, &upgrades);
+    }
+  }
+}
+
+fn reset_fire_cooldown(
+  weapon: Res<Weapon>,
+  mut cooldown: ResMut<FireCooldown>,
+  upgrades: Res<Upgrades>,
+) {
+  *cooldown = FireCooldown::for_weapon(weapon.0, &upgrades);
+}
+
+fn build_projectile_visuals(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+  commands.insert_resource(ProjectileVisuals {
+    mesh: meshes.add(
+      Path::circle(PROJECTILE_RADIUS, PROJECTILE_SEGMENTS)
+        .build_triangle_mesh()
+        .expect("circles are simple polygons"),
+    ),
+    materials: WeaponKind::ALL
+      .into_iter()
+      .map(|kind| (kind, materials.add(kind.stats().color)))
+      .collect(),
+  });
+}
+
+// Fires from the player toward the reticle.
+fn fire(
+  mut commands: Commands,
+  visuals: Res<ProjectileVisuals>,
+  (weapon, upgrades): (Res<Weapon>, Res<Upgrades>),
+  (mut cooldown, time): (ResMut<FireCooldown>, Res<Time>),
+  blockers: Res<ArenaBlockers>,
+  player: Query<&Transform, (With<Player>, Without<Reticle>)>,
+  reticle: Query<&Transform, (With<Reticle>, Without<Player>)>,
+) {
+  if !cooldown.0.tick(time.delta()).just_finished() {
+    return;
+  }
+  let (Ok(player), Ok(reticle)) = (player.get_single(), reticle.get_single()) else {
+    return;
+  };
+  let from = player.translation.xy();
+  let to = reticle.translation.xy();
+  let Some(direction) = (to - from).try_normalize() else {
+    return;
+  };
+
+  let stats = weapon.0.stats();
+  let damage = upgrades.shot_damage(stats.damage);
+  if weapon.0 == WeaponKind::Beam {
+    // Pierces enemies, but not the first wall or obstacle in its way. Only how far along the beam
+    // that is counts, in case the sweep pushed sideways off a corner.
+    let stop = blockers.sweep(
+      &Collider::Circle {
+        radius: BEAM_HALF_WIDTH,
+      },
+      from,
+      to,
+    );
+    let to = from + direction * (stop - from).dot(direction).max(0.);
+    let half_size = Vec2::new(from.distance(to) / 2., BEAM_HALF_WIDTH);
+    commands.spawn((
+      Transform::from_translation(((from + to) / 2.).extend(0.))
+        .with_rotation(Quat::from_rotation_z(direction.to_angle())),
+      DamageArea {
+        damage,
+        half_size,
+        source: DamageSource::Shot,
+      },
+      Collider::Obb { half_size },
+      CollisionLayer::DamageArea,
+    ));
+    return;
+  }
+
+  for i in 0..stats.projectiles {
+    let angle = match stats.projectiles {
+      1 => 0.,
+      n => stats.spread * (i as f32 / (n - 1) as f32 - 0.5),
+    };
+    commands.spawn((
+      Transform::from_translation(from.extend(0.5)),
+      Projectile {
+        velocity: Vec2::from_angle(angle).rotate(direction) * stats.speed,
+        damage,
+        pierce: stats.pierce,
+        homing: weapon.0 == WeaponKind::Homing,
+        hit: Vec::new(),
+        lifetime: Timer::from_seconds(PROJECTILE_LIFETIME_SECS, TimerMode::Once),
+      },
+      Collider::Circle {
+        radius: PROJECTILE_RADIUS,
+      },
+      CollisionLayer::Projectile,
+      Mesh2d(visuals.mesh.clone()),
+      MeshMaterial2d(visuals.materials[&weapon.0].clone()),
+      StateScoped(AppState::Playing),
+    ));
+  }
+}
+
+fn move_projectiles(
+  mut projectiles: Query<(&mut Transform, &mut Projectile), Without<Enemy>>,
+  enemies: Query<&Transform, With<Enemy>>,
+  index: Res<SpatialIndex>,
+  time: Res<Time>,
+) {
+  let dt = time.delta_secs();
+  for (mut transform, mut projectile) in projectiles.iter_mut() {
+    let position = transform.translation.xy();
+    if projectile.homing {
+      // Turn toward the nearest enemy in range, at a limited rate so shots still arc. Last frame's
+      // index is close enough for picking a target.
+      let range = Rect::from_center_half_size(position, Vec2::splat(HOMING_RANGE));
+      let nearest = index
+        .query_rect(range, CollisionLayer::Enemy)
+        .filter_map(|enemy| enemies.get(enemy).ok())
+        .map(|enemy| enemy.translation.xy())
+        .filter(|target| target.distance(position) <= HOMING_RANGE)
+        .min_by(|a, b| {
+          a.distance_squared(position)
+            .total_cmp(&b.distance_squared(position))
+        });
+      if let Some(target) = nearest {
+        let wanted = projectile.velocity.angle_to(target - position);
+        let turn = wanted.clamp(-HOMING_TURN_RATE * dt, HOMING_TURN_RATE * dt);
+        projectile.velocity = Vec2::from_angle(turn).rotate(projectile.velocity);
+      }
+    }
+    transform.translation += (projectile.velocity * dt).extend(0.);
+  }
+}
+
+// Each projectile counts as one attack, which landed if it hit anything along the way.
+fn retire_projectile(
+  commands: &mut Commands,
+  resolved_events: &mut EventWriter<DamageAreaResolvedEvent>,
+  entity: Entity,
+  projectile: &Projectile,
+) {
+  resolved_events.send(DamageAreaResolvedEvent {
+    hits: projectile.hit.len() as u32,
+  });
+  commands.entity(entity).despawn_recursive();
+}
+
+fn expire_projectiles(
+  mut commands: Commands,
+  mut projectiles: Query<(Entity, &Transform, &mut Projectile)>,
+  play_area: Res<PlayArea>,
+  time: Res<Time>,
+  mut resolved_events: EventWriter<DamageAreaResolvedEvent>,
+) {
+  let bounds = Rect::from_center_size(Vec2::ZERO, play_area.size_world);
+  for (entity, transform, mut projectile) in projectiles.iter_mut() {
+    let finished = projectile.lifetime.tick(time.delta()).finished();
+    if finished || !bounds.contains(transform.translation.xy()) {
+      retire_projectile(&mut commands, &mut resolved_events, entity, &projectile);
+    }
+  }
+}
+
+fn projectile_hits(
+  mut commands: Commands,
+  mut collision_events: EventReader<CollisionEvent>,
+  mut projectiles: Query<&mut Projectile>,
+  enemies: Query<&Transform, With<Enemy>>,
+  mut damage_events: EventWriter<DamageEvent>,
+  mut resolved_events: EventWriter<DamageAreaResolvedEvent>,
+) {
+  for (projectile_entity, enemy) in collision_events
+    .read()
+    .filter_map(|event| event.between(CollisionLayer::Projectile, CollisionLayer::Enemy))
+  {
+    let (Ok(mut projectile), Ok(enemy_transform)) =
+      (projectiles.get_mut(projectile_entity), enemies.get(enemy))
+    else {
+      continue;
+    };
+    // Already used up by an earlier enemy this frame, or already hit this one.
+    if projectile.pierce == 0 || projectile.hit.contains(&enemy) {
+      continue;
+    }
+
+    damage_events.send(DamageEvent {
+      target: enemy,
+      amount: projectile.damage,
+      source: DamageSource::Shot,
+      attack: Some(projectile_entity),
+      position: enemy_transform.translation.xy(),
+    });
+    projectile.hit.push(enemy);
+    projectile.pierce -= 1;
+    if projectile.pierce == 0 {
+      retire_projectile(
+        &mut commands,
+        &mut resolved_events,
+        projectile_entity,
+        &projectile,
+      );
+    }
+  }
+}
+
+fn stop_projectiles_at_obstacles(
+  mut commands: Commands,
+  mut collision_events: EventReader<CollisionEvent>,
+  mut projectiles: Query<&mut Projectile>,
+  mut resolved_events: EventWriter<DamageAreaResolvedEvent>,
+) {
+  for (projectile_entity, _) in collision_events
+    .read()
+    .filter_map(|event| event.between(CollisionLayer::Projectile, CollisionLayer::Obstacle))
+  {
+    let Ok(mut projectile) = projectiles.get_mut(projectile_entity) else {
+      continue;
+    };
+    // Skip ones already despawned for running out of pierce.
+    if projectile.pierce > 0 {
+      projectile.pierce = 0;
+      retire_projectile(
+        &mut commands,
+        &mut resolved_events,
+        projectile_entity,
+        &projectile,
+      );
+    }
+  }
+}
+
+#[derive(Component)]
+struct WeaponDisplay;
+
+fn spawn_weapon_display(mut commands: Commands) {
+  commands.spawn((
+    Text::default(),
+    TextFont {
+      font_size: 18.0,
+      ..default()
+    },
+    Node {
+      position_type: PositionType::Absolute,
+      bottom: Val::Px(15.0),
+      right: Val::Px(15.0),
+      ..default()
+    },
+    WeaponDisplay,
+    StateScoped(AppState::Playing),
+  ));
+}
+
+fn update_weapon_display(weapon: Res<Weapon>, mut text: Query<&mut Text, With<WeaponDisplay>>) {
+  for mut text in text.iter_mut() {
+    text.0 = format!("{} (scroll to switch)", weapon.0.name());
+  }
+}
@@ -1,16 +1,26 @@
 use bevy::{
   input::common_conditions::{input_just_pressed, input_toggle_active},
   prelude::*,
-  window::WindowResolution,
+  render::camera::{ScalingMode, Viewport},
+  window::{PrimaryWindow, WindowMode, WindowResized, WindowResolution},
 };
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
-use crate::AppState;
+use crate::{
+  path::Path,
+  settings::{DisplayMode, Settings},
+  AppState,
+};
 
 pub struct WindowSetupPlugin;
 
-const PIXELS_PER_METER: f32 = 100.0;
+// The arena is always this big in world units, whatever the window's size. It's scaled up as far as
+// it fits, with bars filling whatever space is left over.
+const ARENA_SIZE: Vec2 = Vec2::new(16.0, 9.0);
+// In logical pixels.
+const DEFAULT_WINDOW_SIZE: Vec2 = Vec2::new(1600.0, 900.0);
 const BACKGROUND_COLOR: Color = Color::srgb(64.0 / 255.0, 67.0 / 255.0, 78.0 / 255.0);
+const LETTERBOX_COLOR: Color = Color::BLACK;
 
 impl Plugin for WindowSetupPlugin {
   fn build(&self, app: &mut App) {
@@ -23,11 +33,19 @@ impl Plugin for WindowSetupPlugin {
         OnEnter(AppState::Loading),
         (
           (size_window, spawn_camera),
-          (configure_play_area, toggle_os_cursor),
+          (configure_play_area, letterbox_camera, toggle_os_cursor),
           exit_loading,
         )
           .chain(),
       )
+      .add_systems(
+        Update,
+        (
+          apply_display_mode.run_if(resource_changed::<Settings>),
+          (configure_play_area, letterbox_camera).run_if(on_event::<WindowResized>),
+        )
+          .chain(),
+      )
       .add_systems(Update, close.run_if(input_just_pressed(KeyCode::Escape)))
       .add_plugins(
         WorldInspectorPlugin::new().run_if(input_toggle_active(false, KeyCode::Backquote)),
@@ -35,6 +53,7 @@ impl Plugin for WindowSetupPlugin {
   }
 }
 
+// Replaced whenever the window is resized, since the mapping from window to world changes with it.
 #[derive(Resource)]
 pub struct PlayArea {
   pub size_world: Vec2,
@@ -44,20 +63,44 @@ pub struct PlayArea {
 fn size_window(mut windows: Query<&mut Window>) {
   let mut window = windows.single_mut();
   let scale_factor = window.scale_factor();
-  window.resolution = WindowResolution::new(1600.0 * scale_factor, 900.0 * scale_factor)
-    .with_scale_factor_override(scale_factor);
+  window.resolution = WindowResolution::new(
+    DEFAULT_WINDOW_SIZE.x * scale_factor,
+    DEFAULT_WINDOW_SIZE.y * scale_factor,
+  )
+  .with_scale_factor_override(scale_factor);
   window.position.center(MonitorSelection::Current);
 }
 
-fn spawn_camera(mut commands: Commands) {
-  commands.insert_resource(ClearColor(BACKGROUND_COLOR));
+fn spawn_camera(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+  // The camera only draws inside the letterbox, but clearing covers the whole window. So the clear
+  // color goes to the bars, and the arena gets a backdrop of its own.
+  commands.insert_resource(ClearColor(LETTERBOX_COLOR));
   commands.spawn((
     Camera2d,
     OrthographicProjection {
-      scale: 1.0 / PIXELS_PER_METER,
+      scaling_mode: ScalingMode::Fixed {
+        width: ARENA_SIZE.x,
+        height: ARENA_SIZE.y,
+      },
       ..OrthographicProjection::default_2d()
     },
   ));
+  // Oversized, so screen shake never pulls its edge into view.
+  commands.spawn((
+    Transform::from_xyz(0.0, 0.0, -2.0),
+    Mesh2d(
+      meshes.add(
+        Path::rect(ARENA_SIZE)
+          .build_triangle_mesh()
+          .expect("rectangles are simple polygons"),
+      ),
+    ),
+    MeshMaterial2d(materials.add(BACKGROUND_COLOR)),
+  ));
 }
 
 fn toggle_os_cursor(mut windows: Query<&mut Window>) {
@@ -76,39 +119,74 @@ fn toggle_os_cursor(mut windows: Query<&mut Window>) {
   };
 }
 
-fn configure_play_area(
-  mut commands: Commands,
-  window_query: Query<&Window>,
-  camera_query: Query<(&GlobalTransform, &OrthographicProjection), With<Camera>>,
-) {
-  let window_to_world = {
-    let (camera_transform, projection) = camera_query.single();
-    let window_size = window_query.single().size();
-    let center = camera_transform.translation().truncate();
-    let scale = projection.scale;
-
-    let half_width = (window_size.x / 2.0) * scale;
-    let half_height = (window_size.y / 2.0) * scale;
-    let left = center.x - half_width;
-    let bottom = center.y - half_height;
-    Box::new(move |position: Vec2| {
-      Vec2::new(
-        left + position.x * scale,
-        bottom + (window_size.y - position.y) * scale,
-      )
-    })
+// The arena, as big as it fits and centered in the window.
+fn fit_arena(window_size: Vec2) -> (Vec2, f32) {
+  let pixels_per_unit = (window_size / ARENA_SIZE).min_element();
+  (
+    (window_size - ARENA_SIZE * pixels_per_unit) / 2.0,
+    pixels_per_unit,
+  )
+}
+
+fn configure_play_area(mut commands: Commands, windows: Query<&Window, With<PrimaryWindow>>) {
+  let Ok(window) = windows.get_single() else {
+    return;
   };
+  let window_size = window.size();
+  let (_, pixels_per_unit) = fit_arena(window_size);
+  // Minimized, most likely. The old mapping will do until the window comes back.
+  if pixels_per_unit <= 0.0 {
+    return;
+  }
 
+  // The arena's center is both the world origin and the window's center.
+  let window_to_world = Box::new(move |position: Vec2| {
+    let offset = (position - window_size / 2.0) / pixels_per_unit;
+    Vec2::new(offset.x, -offset.y)
+  });
   commands.insert_resource(PlayArea {
-    size_world: (window_to_world(Vec2::new(
-      window_query.single().width(),
-      window_query.single().height(),
-    )) - window_to_world(Vec2::ZERO))
-    .abs(),
+    size_world: ARENA_SIZE,
     window_to_world,
   });
 }
 
+fn letterbox_camera(
+  windows: Query<&Window, With<PrimaryWindow>>,
+  mut cameras: Query<&mut Camera, With<Camera2d>>,
+) {
+  let Ok(window) = windows.get_single() else {
+    return;
+  };
+  let window_size = window.physical_size().as_vec2();
+  let (position, pixels_per_unit) = fit_arena(window_size);
+  let size = (ARENA_SIZE * pixels_per_unit).as_uvec2();
+  for mut camera in cameras.iter_mut() {
+    // An empty viewport isn't allowed, so a minimized window just goes without.
+    camera.viewport = (size.min_element() > 0).then(|| Viewport {
+      physical_position: position.as_uvec2(),
+      physical_size: size,
+      ..default()
+    });
+  }
+}
+
+fn apply_display_mode(
+  settings: Res<Settings>,
+  mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+  let Ok(mut window) = windows.get_single_mut() else {
+    return;
+  };
+  let mode = match settings.display_mode {
+    DisplayMode::Windowed => WindowMode::Windowed,
+    DisplayMode::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+    DisplayMode::Fullscreen => WindowMode::Fullscreen(MonitorSelection::Current),
+  };
+  if window.mode != mode {
+    window.mode = mode;
+  }
+}
+
 fn exit_loading(mut state: ResMut<NextState<AppState>>) {
   state.set(AppState::Intro);
 }